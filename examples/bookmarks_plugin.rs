@@ -0,0 +1,191 @@
+//! Reference plugin for the jd plugin resolver protocol.
+//!
+//! Stores items as bookmarks in a JSON file given as the first argument, e.g.:
+//!
+//! ```json
+//! {"constraint": [50, 59], "config": {"plugin": "bookmarks_plugin", "args": ["/path/to/bookmarks.json"]}}
+//! ```
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use johnny::{Item, ID};
+
+const SUPPORTED_VERSIONS: &[u32] = &[1];
+
+type Categories = BTreeMap<usize, (String, Vec<Item>)>;
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Location {
+    Path(PathBuf),
+    Url(String),
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct Bookmark {
+    item: Item,
+    category: String,
+    area: String,
+    location: Location,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct ItemContext {
+    item: Item,
+    category: String,
+    area: String,
+}
+
+struct Store {
+    path: PathBuf,
+    bookmarks: BTreeMap<String, Bookmark>,
+}
+
+impl Store {
+    fn load(path: PathBuf) -> Result<Self> {
+        let bookmarks = match fs::File::open(&path) {
+            Ok(f) => serde_json::from_reader(f)?,
+            Err(_) => BTreeMap::new(),
+        };
+        Ok(Self { path, bookmarks })
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.bookmarks)?)?;
+        Ok(())
+    }
+
+    fn handle(&mut self, method: &str, params: Value) -> Result<Value> {
+        match method {
+            "handshake" => {
+                let offered: Vec<u32> = serde_json::from_value(params["versions"].clone())?;
+                let version = SUPPORTED_VERSIONS
+                    .iter()
+                    .rev()
+                    .find(|v| offered.contains(v))
+                    .ok_or_else(|| anyhow!("no common protocol version"))?;
+                Ok(json!({ "version": version }))
+            }
+            "get" => {
+                let ctx: ItemContext = serde_json::from_value(params)?;
+                let key = format!("{}", ctx.item.id);
+                Ok(serde_json::to_value(
+                    self.bookmarks.get(&key).map(|b| b.location.clone()),
+                )?)
+            }
+            "collect" => Ok(self.collect()),
+            "set" => {
+                let location: Location = serde_json::from_value(params["source"].clone())?;
+                let ctx: ItemContext = serde_json::from_value(params)?;
+                self.bookmarks.insert(
+                    format!("{}", ctx.item.id),
+                    Bookmark {
+                        item: ctx.item,
+                        category: ctx.category,
+                        area: ctx.area,
+                        location,
+                    },
+                );
+                self.save()?;
+                Ok(Value::Null)
+            }
+            "remove" => {
+                let ctx: ItemContext = serde_json::from_value(params)?;
+                self.bookmarks.remove(&format!("{}", ctx.item.id));
+                self.save()?;
+                Ok(Value::Null)
+            }
+            "rename_category" => {
+                let category: usize = serde_json::from_value(params["category"].clone())?;
+                let new_name: String = serde_json::from_value(params["new_name"].clone())?;
+                for b in self.bookmarks.values_mut() {
+                    if b.item.id.category == category {
+                        b.category = new_name.clone();
+                    }
+                }
+                self.save()?;
+                Ok(Value::Null)
+            }
+            "rename_item" => {
+                let old: ItemContext = serde_json::from_value(params["old"].clone())?;
+                let new: ItemContext = serde_json::from_value(params["new"].clone())?;
+                let mut bookmark = self
+                    .bookmarks
+                    .remove(&format!("{}", old.item.id))
+                    .ok_or_else(|| anyhow!("unknown item: {}", old.item.id))?;
+                bookmark.item = new.item;
+                self.bookmarks
+                    .insert(format!("{}", bookmark.item.id), bookmark);
+                self.save()?;
+                Ok(Value::Null)
+            }
+            _ => bail!("unknown method: {}", method),
+        }
+    }
+
+    fn collect(&self) -> Value {
+        let mut areas: BTreeMap<usize, (String, Categories)> = BTreeMap::new();
+
+        for b in self.bookmarks.values() {
+            let ID { category, .. } = b.item.id;
+            let (_, categories) = areas
+                .entry(category / 10)
+                .or_insert_with(|| (b.area.clone(), BTreeMap::new()));
+            categories
+                .entry(category)
+                .or_insert_with(|| (b.category.clone(), Vec::new()))
+                .1
+                .push(b.item.clone());
+        }
+
+        let areas = areas
+            .into_iter()
+            .map(|(a, (name, categories))| {
+                let categories = categories
+                    .into_iter()
+                    .map(|(id, (name, items))| json!({ "id": id, "name": name, "items": items }))
+                    .collect::<Vec<_>>();
+                json!({ "bounds": [a * 10, a * 10 + 9], "name": name, "categories": categories })
+            })
+            .collect::<Vec<_>>();
+
+        Value::Array(areas)
+    }
+}
+
+fn main() -> Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("usage: bookmarks_plugin <store.json>"))?;
+    let mut store = Store::load(path)?;
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let request: Request = serde_json::from_str(&line?)?;
+        let response = match store.handle(&request.method, request.params) {
+            Ok(result) => json!({ "result": result }),
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+        writeln!(stdout, "{}", response)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use clap::Parser;
+
+use johnny::JohnnyDecimal;
+
+use super::JCommand;
+
+#[derive(Parser)]
+pub struct MigrateCommand {
+    category: usize,
+
+    /// Name of the resolver taking over the category.
+    #[clap(long = "to-resolver")]
+    to_resolver: String,
+}
+
+impl JCommand for MigrateCommand {
+    fn run(&self, mut jd: JohnnyDecimal) -> Result<()> {
+        let migrated = jd.migrate_category(self.category, &self.to_resolver)?;
+        for item in migrated.iter() {
+            println!("{}", item);
+        }
+        bunt::println!(
+            "migrated {[bold]} item(s) to {[bold]}",
+            migrated.len(),
+            self.to_resolver
+        );
+        if let Some(path) = &jd.config().path {
+            println!(
+                "updated {} (the previous version is kept as .bak)",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
+    fn run_json(&self, mut jd: JohnnyDecimal) -> Result<()> {
+        let migrated = jd.migrate_category(self.category, &self.to_resolver)?;
+        println!("{}", serde_json::to_string(&migrated)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+
+use johnny::{JohnnyDecimal, Location, Query, ID};
+
+use super::JCommand;
+
+#[derive(Parser)]
+pub struct CdCommand {
+    /// An AC.ID code, a category like 11, an area like 10-19 or a query picking the best item like `jd jump`.
+    #[clap(allow_hyphen_values = true, required = true)]
+    target: Vec<String>,
+}
+
+/// Parses an area like `10-19`.
+fn parse_area(s: &str) -> Option<(usize, usize)> {
+    let (lower, upper) = s.split_once('-')?;
+    let (lower, upper) = (lower.parse::<usize>().ok()?, upper.parse::<usize>().ok()?);
+    if lower < 100 && lower % 10 == 0 && upper == lower + 9 {
+        Some((lower, upper))
+    } else {
+        None
+    }
+}
+
+impl CdCommand {
+    /// Finds the directory to change to.
+    fn directory(&self, jd: &JohnnyDecimal) -> Result<PathBuf> {
+        let target = self.target.join(" ");
+
+        if let Some(bounds) = parse_area(&target) {
+            return jd
+                .locate_area(bounds)?
+                .ok_or_else(|| anyhow!("area {}-{} has no folder", bounds.0, bounds.1));
+        }
+
+        if let Ok(category) = target.parse::<usize>() {
+            return jd
+                .locate_category(category)?
+                .ok_or_else(|| anyhow!("category {:02} has no folder", category));
+        }
+
+        let id = match target.parse::<ID>() {
+            Ok(id) => id,
+            Err(_) => {
+                let query = target.parse::<Query>()?;
+                jd.jump(&query)?
+                    .ok_or_else(|| anyhow!("no item matches '{}'", target))?
+                    .id
+            }
+        };
+
+        let path = match jd.locate(&id)? {
+            Some(Location::Path(p)) if p.exists() => p,
+            Some(Location::URL(url)) => bail!("{} is a URL: {}", id, url),
+            _ => bail!("{} has no folder", id),
+        };
+        super::record_use(jd, &id);
+
+        // Items stored as a single file are reached through the folder holding them.
+        if path.is_dir() {
+            Ok(path)
+        } else {
+            path.parent()
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow!("{} has no folder", id))
+        }
+    }
+}
+
+impl JCommand for CdCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        println!("{}", self.directory(&jd)?.to_string_lossy());
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        println!("{}", serde_json::to_string(&self.directory(&jd)?)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use clap::{App, AppSettings, Arg, ArgSettings, IntoApp, Parser};
+
+use johnny::{HitKind, JohnnyDecimal};
+
+use serde::Serialize;
+
+use super::{JCommand, Root};
+
+/// A completion candidate, with a description shown by the shells that support it.
+#[derive(Serialize)]
+struct Candidate {
+    value: String,
+    description: String,
+}
+
+/// What the positional arguments of each command are completed with.
+fn positional_kinds(path: &[&str], position: usize) -> &'static [HitKind] {
+    match (path, position) {
+        (["cd"], _) => &[HitKind::Area, HitKind::Category, HitKind::Item],
+        (["open"], 0)
+        | (["item", "open" | "find" | "mv" | "rm" | "rename" | "status" | "tag"], 0) => {
+            &[HitKind::Item]
+        }
+        (["ls"], 0) | (["cat", "rename" | "migrate"], 0) | (["item", "mv"], 1) => {
+            &[HitKind::Category]
+        }
+        _ => &[],
+    }
+}
+
+/// What the value of an option is completed with.
+fn option_kinds(long: &str) -> &'static [HitKind] {
+    match long {
+        "category" => &[HitKind::Category],
+        "id" => &[HitKind::Item],
+        _ => &[],
+    }
+}
+
+#[derive(Parser)]
+pub struct CompleteCommand {
+    /// The words typed after `jd`, the last one being completed.
+    #[clap(allow_hyphen_values = true)]
+    words: Vec<String>,
+}
+
+impl CompleteCommand {
+    fn candidates(&self, jd: &JohnnyDecimal) -> Vec<Candidate> {
+        let (partial, typed) = match self.words.split_last() {
+            Some((partial, typed)) => (partial.as_str(), typed),
+            None => ("", &[][..]),
+        };
+
+        let root = Root::into_app();
+        let mut app = &root;
+        let mut path = Vec::new();
+        let mut position = 0;
+        let mut option: Option<&str> = None;
+
+        for word in typed {
+            // The word is the value of the previous option.
+            if option.take().is_some() {
+                continue;
+            }
+
+            if word.starts_with('-') {
+                let is_flag = |a: &&Arg| match word.strip_prefix("--") {
+                    Some(long) => a.get_long() == Some(long),
+                    None => word.chars().count() == 2 && word.chars().nth(1) == a.get_short(),
+                };
+                option = app
+                    .get_arguments()
+                    .find(is_flag)
+                    .filter(|a| a.is_set(ArgSettings::TakesValue))
+                    .and_then(|a| a.get_long());
+                continue;
+            }
+
+            match app.get_subcommands().find(|s| s.get_name() == word) {
+                Some(sub) => {
+                    app = sub;
+                    path.push(sub.get_name());
+                    position = 0;
+                }
+                None => position += 1,
+            }
+        }
+
+        if let Some(long) = option {
+            return entries(jd, option_kinds(long), partial);
+        }
+
+        if partial.starts_with('-') {
+            return app
+                .get_arguments()
+                .filter(|a| !a.is_set(ArgSettings::Hidden))
+                .filter_map(|a| a.get_long().map(|l| (format!("--{}", l), a.get_help())))
+                .filter(|(flag, _)| flag.starts_with(partial))
+                .map(|(value, help)| Candidate {
+                    value,
+                    description: String::from(help.unwrap_or_default()),
+                })
+                .collect();
+        }
+
+        if app.has_subcommands() {
+            return subcommands(app, partial);
+        }
+
+        entries(jd, positional_kinds(&path, position), partial)
+    }
+}
+
+fn subcommands(app: &App, partial: &str) -> Vec<Candidate> {
+    app.get_subcommands()
+        .filter(|s| !s.is_set(AppSettings::Hidden))
+        .filter(|s| s.get_name().starts_with(partial))
+        .map(|s| Candidate {
+            value: String::from(s.get_name()),
+            description: String::from(s.get_about().unwrap_or_default()),
+        })
+        .collect()
+}
+
+/// Lists the areas, categories and items of the index whose code starts with the partial input.
+fn entries(jd: &JohnnyDecimal, kinds: &[HitKind], partial: &str) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    for area in jd.index.list_areas() {
+        if kinds.contains(&HitKind::Area) {
+            candidates.push(Candidate {
+                value: format!("{:02}-{:02}", area.bounds.0, area.bounds.1),
+                description: area.name.clone(),
+            });
+        }
+
+        for category in area.list_categories() {
+            if kinds.contains(&HitKind::Category) {
+                candidates.push(Candidate {
+                    value: format!("{:02}", category.id),
+                    description: category.name.clone(),
+                });
+            }
+
+            if kinds.contains(&HitKind::Item) {
+                candidates.extend(category.list_items().into_iter().map(|item| Candidate {
+                    value: format!("{}", item.id),
+                    description: item.name,
+                }));
+            }
+        }
+    }
+
+    candidates.retain(|c| c.value.starts_with(partial));
+    candidates
+}
+
+impl JCommand for CompleteCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        for candidate in self.candidates(&jd) {
+            println!("{}\t{}", candidate.value, candidate.description);
+        }
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        println!("{}", serde_json::to_string(&self.candidates(&jd))?);
+        Ok(())
+    }
+}
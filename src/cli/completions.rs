@@ -0,0 +1,63 @@
+use anyhow::Result;
+use clap::Parser;
+
+use johnny::JohnnyDecimal;
+
+use super::shell_init::Shell;
+use super::JCommand;
+
+const BASH_COMPLETIONS: &str = r#"_jd() {
+    local IFS=$'\n'
+    COMPREPLY=($(command jd __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null | cut -f1))
+}
+
+complete -o default -F _jd jd
+"#;
+
+const ZSH_COMPLETIONS: &str = r#"_jd() {
+    local -a candidates
+    candidates=("${(@f)$(command jd __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+    candidates=("${(@)${(@)candidates:#}//$'\t'/:}")
+    _describe 'jd' candidates || _files
+}
+
+compdef _jd jd
+"#;
+
+const FISH_COMPLETIONS: &str = r#"function __jd_complete
+    set -l words (commandline -opc)
+    set -e words[1]
+    command jd __complete -- $words (commandline -ct) 2>/dev/null
+end
+
+complete -c jd -f -a '(__jd_complete)'
+complete -c jd -n '__fish_seen_subcommand_from add_file' -F
+"#;
+
+#[derive(Parser)]
+pub struct CompletionsCommand {
+    /// The shell to complete for: bash, zsh or fish.
+    shell: Shell,
+}
+
+impl CompletionsCommand {
+    /// Prints the script to source from the shell's startup file.
+    pub fn print(&self) -> Result<()> {
+        match self.shell {
+            Shell::Bash => print!("{}", BASH_COMPLETIONS),
+            Shell::Zsh => print!("{}", ZSH_COMPLETIONS),
+            Shell::Fish => print!("{}", FISH_COMPLETIONS),
+        }
+        Ok(())
+    }
+}
+
+impl JCommand for CompletionsCommand {
+    fn run(&self, _jd: JohnnyDecimal) -> Result<()> {
+        self.print()
+    }
+
+    fn run_json(&self, _jd: JohnnyDecimal) -> Result<()> {
+        self.print()
+    }
+}
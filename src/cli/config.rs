@@ -0,0 +1,164 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use johnny::{JohnnyDecimal, Overlap};
+
+use serde::Serialize;
+
+use super::JCommand;
+
+#[derive(Serialize)]
+struct CategoryView {
+    id: usize,
+    name: String,
+    resolver: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AreaView {
+    bounds: (usize, usize),
+    name: String,
+    resolvers: Vec<String>,
+    categories: Vec<CategoryView>,
+}
+
+#[derive(Serialize)]
+struct CheckView {
+    areas: Vec<AreaView>,
+    duplicate_names: Vec<String>,
+    overlaps: Vec<Overlap>,
+}
+
+/// Categories of the index that no resolver serves, their items can't be found.
+fn unresolved(jd: &JohnnyDecimal) -> Vec<String> {
+    jd.index
+        .list_areas()
+        .into_iter()
+        .flat_map(|a| a.list_categories())
+        .filter(|c| jd.describe_resolver(c.id).is_none())
+        .map(|c| format!("{}", c))
+        .collect()
+}
+
+/// Warns about the problems of the resolver table that commands still work around:
+/// categories without a resolver and resolvers only told apart by their order.
+/// `jd config check` lists every overlap.
+pub fn warn(jd: &JohnnyDecimal) {
+    for category in unresolved(jd) {
+        bunt::eprintln!(
+            "{[yellow]} category {} has no resolver",
+            "warning:",
+            category
+        );
+    }
+    for overlap in jd.config().overlaps().into_iter().filter(|o| o.tie) {
+        bunt::eprintln!("{[yellow]} {}", "warning:", overlap);
+    }
+}
+
+#[derive(Parser)]
+pub struct CheckCommand {}
+
+impl CheckCommand {
+    /// Fails when commands would reject the table or lose track of items.
+    fn verdict(&self, jd: &JohnnyDecimal) -> Result<()> {
+        let errors = jd.config().duplicate_names().len() + unresolved(jd).len();
+        if errors > 0 {
+            bail!("the resolver table has {} error(s)", errors);
+        }
+        Ok(())
+    }
+
+    fn table(&self, jd: &JohnnyDecimal) -> Vec<AreaView> {
+        jd.index
+            .list_areas()
+            .into_iter()
+            .map(|area| {
+                let mut resolvers = Vec::new();
+                for category in area.bounds.0..=area.bounds.1 {
+                    if let Some(r) = jd.describe_resolver(category) {
+                        if !resolvers.contains(&r) {
+                            resolvers.push(r);
+                        }
+                    }
+                }
+
+                let categories = area
+                    .list_categories()
+                    .into_iter()
+                    .map(|c| CategoryView {
+                        id: c.id,
+                        name: c.name.clone(),
+                        resolver: jd.describe_resolver(c.id),
+                    })
+                    .collect();
+
+                AreaView {
+                    bounds: area.bounds,
+                    name: area.name.clone(),
+                    resolvers,
+                    categories,
+                }
+            })
+            .collect()
+    }
+}
+
+impl JCommand for CheckCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        let mut gaps = 0;
+
+        for area in self.table(&jd) {
+            let label = format!("{:02}-{:02} {}", area.bounds.0, area.bounds.1, area.name);
+            if area.resolvers.is_empty() {
+                gaps += 1;
+                bunt::println!("[{[bold+blue]}] {[red]}", label, "no resolver");
+            } else {
+                bunt::println!("[{[bold+blue]}] {}", label, area.resolvers.join(", "));
+            }
+
+            for category in area.categories {
+                let label = format!("{:02} {}", category.id, category.name);
+                match category.resolver {
+                    Some(r) => bunt::println!("  {[green]} {}", label, r),
+                    None => {
+                        gaps += 1;
+                        bunt::println!("  {[green]} {[red]}", label, "no resolver");
+                    }
+                }
+            }
+        }
+
+        if gaps > 0 {
+            bunt::println!(
+                "{[yellow]} area(s) or category(ies) without a resolver",
+                gaps
+            );
+        }
+
+        let duplicates = jd.config().duplicate_names();
+        for duplicate in duplicates.iter() {
+            bunt::println!("{[red]} {}", "error:", duplicate);
+        }
+
+        for overlap in jd.config().overlaps() {
+            if overlap.tie {
+                bunt::println!("{[yellow]} {}", "warning:", overlap);
+            } else {
+                bunt::println!("{[dimmed]} {}", "note:", overlap);
+            }
+        }
+
+        self.verdict(&jd)
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        let view = CheckView {
+            areas: self.table(&jd),
+            duplicate_names: jd.config().duplicate_names(),
+            overlaps: jd.config().overlaps(),
+        };
+        println!("{}", serde_json::to_string(&view)?);
+        self.verdict(&jd)
+    }
+}
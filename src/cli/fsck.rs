@@ -0,0 +1,47 @@
+use anyhow::Result;
+use clap::Parser;
+
+use johnny::JohnnyDecimal;
+
+use serde::Serialize;
+
+use super::JCommand;
+
+#[derive(Serialize)]
+struct DanglingView {
+    id: String,
+    name: String,
+    target: String,
+}
+
+#[derive(Parser)]
+pub struct FsckCommand {}
+
+impl JCommand for FsckCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        for (item, target) in jd.dangling_links()? {
+            bunt::println!(
+                "{} -> {} [{[red]}]",
+                item,
+                target.to_string_lossy(),
+                "dangling"
+            );
+        }
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        let views = jd
+            .dangling_links()?
+            .into_iter()
+            .map(|(item, target)| DanglingView {
+                id: format!("{}", item.id),
+                name: item.name,
+                target: target.to_string_lossy().to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        println!("{}", serde_json::to_string(&views)?);
+        Ok(())
+    }
+}
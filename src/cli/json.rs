@@ -1,6 +1,8 @@
+use std::path::PathBuf;
+
 use anyhow::{anyhow, Result};
 
-use johnny::{Category, Item, JohnnyDecimal};
+use johnny::{Category, ContentHit, HitLocation, Item, JohnnyDecimal, Location, SearchHit};
 
 use serde::Serialize;
 
@@ -16,6 +18,52 @@ pub struct ItemView {
     name: String,
     category_name: String,
     category: CategoryView,
+
+    /// Match score, only set for search results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<i64>,
+
+    /// Matched parts of the name, as `[start, end)` character offsets.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    spans: Vec<(usize, usize)>,
+}
+
+/// An item with its location, as listed by `jd pick`.
+#[derive(Serialize)]
+pub struct FlatItemView {
+    #[serde(flatten)]
+    item: ItemView,
+    location: Option<Location>,
+}
+
+/// A search hit of any kind, tagged with its kind.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum HitView {
+    Area {
+        id: String,
+        name: String,
+        score: i64,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        spans: Vec<(usize, usize)>,
+    },
+    Category {
+        id: usize,
+        name: String,
+        score: i64,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        spans: Vec<(usize, usize)>,
+    },
+    Item(ItemView),
+}
+
+#[derive(Serialize)]
+pub struct ContentHitView {
+    #[serde(flatten)]
+    item: ItemView,
+    path: PathBuf,
+    line: usize,
+    snippet: String,
 }
 
 pub struct Viewer<'a> {
@@ -50,8 +98,49 @@ impl<'a> Viewer<'a> {
             category_name: format!("{}", category),
             category: self.category(category),
             name: item.name.clone(),
+            score: None,
+            spans: Vec::new(),
         };
 
         Ok(view)
     }
+
+    pub fn flat_item(&self, item: &Item) -> Result<FlatItemView> {
+        Ok(FlatItemView {
+            item: self.item(item)?,
+            location: super::pick::listed_location(self.jd, &item.id),
+        })
+    }
+
+    pub fn content_hit(&self, hit: &ContentHit) -> Result<ContentHitView> {
+        Ok(ContentHitView {
+            item: self.item(&hit.item)?,
+            path: hit.path.clone(),
+            line: hit.line,
+            snippet: hit.snippet.clone(),
+        })
+    }
+
+    pub fn hit(&self, hit: &SearchHit) -> Result<HitView> {
+        Ok(match &hit.location {
+            HitLocation::Area(bounds) => HitView::Area {
+                id: format!("{:02}-{:02}", bounds.0, bounds.1),
+                name: hit.name.clone(),
+                score: hit.score,
+                spans: hit.spans.clone(),
+            },
+            HitLocation::Category(id) => HitView::Category {
+                id: *id,
+                name: hit.name.clone(),
+                score: hit.score,
+                spans: hit.spans.clone(),
+            },
+            HitLocation::Item(item) => {
+                let mut view = self.item(item)?;
+                view.score = Some(hit.score);
+                view.spans = hit.spans.clone();
+                HitView::Item(view)
+            }
+        })
+    }
 }
@@ -0,0 +1,38 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+
+use johnny::{Item, JohnnyDecimal, Query};
+
+use super::{json, open, JCommand};
+
+#[derive(Parser)]
+pub struct JumpCommand {
+    /// The query, using the same syntax as `jd search`.
+    #[clap(allow_hyphen_values = true)]
+    expr: String,
+}
+
+impl JumpCommand {
+    fn target(&self, jd: &JohnnyDecimal) -> Result<Item> {
+        let query = self.expr.parse::<Query>()?;
+        jd.jump(&query)?
+            .ok_or_else(|| anyhow!("no item matches '{}'", self.expr))
+    }
+}
+
+impl JCommand for JumpCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        let item = self.target(&jd)?;
+        println!("{}", item);
+        open::open_item(&jd, &item.id)
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        let item = self.target(&jd)?;
+        println!(
+            "{}",
+            serde_json::to_string(&json::Viewer::new(&jd).item(&item)?)?
+        );
+        open::open_item(&jd, &item.id)
+    }
+}
@@ -14,6 +14,7 @@ pub struct LocateCommand {
 impl JCommand for LocateCommand {
     fn run(&self, jd: JohnnyDecimal) -> Result<()> {
         if let Some(loc) = jd.locate(&self.id)? {
+            super::record_use(&jd, &self.id);
             println!("{}", loc);
         }
         Ok(())
@@ -21,6 +22,7 @@ impl JCommand for LocateCommand {
 
     fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
         if let Some(loc) = jd.locate(&self.id)? {
+            super::record_use(&jd, &self.id);
             println!("{}", serde_json::to_string(&loc)?);
         }
         Ok(())
@@ -3,11 +3,15 @@ use clap::Parser;
 
 use johnny::JohnnyDecimal;
 
-use super::{json, JCommand};
+use super::{json, pick, JCommand};
 
 #[derive(Parser)]
 pub struct LsCommand {
     category: Option<usize>,
+
+    /// Print one `AC.ID<TAB>Name<TAB>Location` line per item, like `jd pick`.
+    #[clap(long = "flat")]
+    flat: bool,
 }
 
 impl LsCommand {
@@ -27,6 +31,10 @@ impl LsCommand {
 
 impl JCommand for LsCommand {
     fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        if self.flat {
+            return pick::print_flat(&jd, self.category);
+        }
+
         for area in jd.index.list_areas() {
             if let Some(cat_filter) = self.category {
                 if cat_filter / 10 != area.bounds.0 / 10 {
@@ -57,6 +65,10 @@ impl JCommand for LsCommand {
     }
 
     fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        if self.flat {
+            return pick::print_flat_json(&jd, self.category);
+        }
+
         if let Some(cat_filter) = self.category {
             let viewer = json::Viewer::new(&jd);
 
@@ -1,23 +1,38 @@
 mod addurl;
+mod cat_migrate;
 mod cat_rename;
+mod cd;
+mod complete;
+mod completions;
+mod config;
+mod fsck;
 mod init;
 mod json;
+mod jump;
 mod locate;
 mod ls;
 mod mkarea;
 mod mkcat;
 mod mv;
 mod open;
+mod pick;
+mod recent;
 mod relocate;
 mod rename;
 mod rm;
 mod search;
+mod shell_init;
+mod status;
+mod store;
+mod tag;
+#[cfg(target_family = "unix")]
+mod tui;
 
 use anyhow::Result;
 
-use clap::Parser;
+use clap::{AppSettings, Parser};
 
-use johnny::{Config, JohnnyDecimal};
+use johnny::{Config, JohnnyDecimal, ID};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -36,10 +51,42 @@ pub trait JCommand {
     fn run_json(&self, jd: JohnnyDecimal) -> Result<()>;
 }
 
+/// Records a use of the item for `jd recent` and `jd jump`, only warning when it can't be saved.
+fn record_use(jd: &JohnnyDecimal, id: &ID) {
+    if let Err(e) = jd.record_use(id) {
+        bunt::eprintln!(
+            "{[yellow]} couldn't record the use of {}: {}",
+            "warning:",
+            id,
+            e
+        );
+    }
+}
+
 impl Root {
     pub fn run(self) -> Result<()> {
+        // Shell scripts are sourced on every shell start, they shouldn't wait on the index.
+        match &self.command {
+            Cmd::ShellInit(cmd) => return cmd.print(),
+            Cmd::Completions(cmd) => return cmd.print(),
+            _ => {}
+        }
+
         let cfg = Config::load()?;
-        let client = JohnnyDecimal::new(cfg)?;
+        let client = match &self.command {
+            // The check reports what validation would reject instead of failing on it.
+            Cmd::Config(ConfigCmd::Check(_)) => JohnnyDecimal::builder()
+                .config(cfg)
+                .skip_validation()
+                .build()?,
+            // Completions are printed while typing, warnings would get in the way.
+            Cmd::Complete(_) => JohnnyDecimal::new(cfg)?,
+            _ => {
+                let client = JohnnyDecimal::new(cfg)?;
+                config::warn(&client);
+                client
+            }
+        };
 
         if self.json {
             self.command.run_json(client)
@@ -56,6 +103,10 @@ pub enum CategoryCmd {
 
     #[clap(name = "rename")]
     Rename(cat_rename::CatRename),
+
+    /// Move every item of a category to another resolver.
+    #[clap(name = "migrate")]
+    Migrate(cat_migrate::MigrateCommand),
 }
 
 impl JCommand for CategoryCmd {
@@ -63,6 +114,7 @@ impl JCommand for CategoryCmd {
         match self {
             CategoryCmd::Create(cmd) => cmd.run(jd),
             CategoryCmd::Rename(cmd) => cmd.run(jd),
+            CategoryCmd::Migrate(cmd) => cmd.run(jd),
         }
     }
 
@@ -70,6 +122,7 @@ impl JCommand for CategoryCmd {
         match self {
             CategoryCmd::Create(cmd) => cmd.run_json(jd),
             CategoryCmd::Rename(cmd) => cmd.run_json(jd),
+            CategoryCmd::Migrate(cmd) => cmd.run_json(jd),
         }
     }
 }
@@ -96,6 +149,13 @@ pub enum ItemCmd {
 
     #[clap(name = "rename")]
     Rename(rename::RenameCommand),
+
+    #[clap(name = "status")]
+    Status(status::StatusCommand),
+
+    /// Tag an item for `tag:` search terms, or list its tags.
+    #[clap(name = "tag")]
+    Tag(tag::TagCommand),
 }
 
 impl JCommand for ItemCmd {
@@ -108,6 +168,8 @@ impl JCommand for ItemCmd {
             ItemCmd::Locate(cmd) => cmd.run(jd),
             ItemCmd::Remove(cmd) => cmd.run(jd),
             ItemCmd::Rename(cmd) => cmd.run(jd),
+            ItemCmd::Status(cmd) => cmd.run(jd),
+            ItemCmd::Tag(cmd) => cmd.run(jd),
         }
     }
 
@@ -120,6 +182,8 @@ impl JCommand for ItemCmd {
             ItemCmd::Locate(cmd) => cmd.run_json(jd),
             ItemCmd::Remove(cmd) => cmd.run_json(jd),
             ItemCmd::Rename(cmd) => cmd.run_json(jd),
+            ItemCmd::Status(cmd) => cmd.run_json(jd),
+            ItemCmd::Tag(cmd) => cmd.run_json(jd),
         }
     }
 }
@@ -143,6 +207,54 @@ impl JCommand for AreaCmd {
     }
 }
 
+#[derive(Parser)]
+enum StoreCmd {
+    /// Drop blobs no longer referenced by any item.
+    #[clap(name = "gc")]
+    Gc(store::GcCommand),
+
+    /// List content stored under more than one item.
+    #[clap(name = "dupes")]
+    Dupes(store::DupesCommand),
+}
+
+impl JCommand for StoreCmd {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        match self {
+            StoreCmd::Gc(cmd) => cmd.run(jd),
+            StoreCmd::Dupes(cmd) => cmd.run(jd),
+        }
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        match self {
+            StoreCmd::Gc(cmd) => cmd.run_json(jd),
+            StoreCmd::Dupes(cmd) => cmd.run_json(jd),
+        }
+    }
+}
+
+#[derive(Parser)]
+enum ConfigCmd {
+    /// Print the resolver serving every area and category.
+    #[clap(name = "check")]
+    Check(config::CheckCommand),
+}
+
+impl JCommand for ConfigCmd {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        match self {
+            ConfigCmd::Check(cmd) => cmd.run(jd),
+        }
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        match self {
+            ConfigCmd::Check(cmd) => cmd.run_json(jd),
+        }
+    }
+}
+
 #[derive(Parser)]
 enum Cmd {
     #[clap(name = "init")]
@@ -157,6 +269,43 @@ enum Cmd {
     /// Open an ID.
     Open(open::OpenCommand),
 
+    /// List items for fzf, rofi or dmenu, and open the one picked with `--select`.
+    #[clap(name = "pick")]
+    Pick(pick::PickCommand),
+
+    /// List the items opened or located lately.
+    #[clap(name = "recent")]
+    Recent(recent::RecentCommand),
+
+    /// Open the most frecent item matching a query.
+    #[clap(name = "jump")]
+    Jump(jump::JumpCommand),
+
+    /// Print the folder of an item, category or area, see `shell-init` to change to it.
+    #[clap(name = "cd")]
+    Cd(cd::CdCommand),
+
+    /// Print a script to source in bash, zsh or fish for `jd cd` and its `jdc` shorthand.
+    #[clap(name = "shell-init")]
+    ShellInit(shell_init::ShellInitCommand),
+
+    /// Print a completion script to source in bash, zsh or fish.
+    #[clap(name = "completions")]
+    Completions(completions::CompletionsCommand),
+
+    // Called by the completion scripts with the words typed so far.
+    #[clap(name = "__complete", setting = AppSettings::Hidden)]
+    Complete(complete::CompleteCommand),
+
+    /// Browse areas, categories and items in a full-screen interface.
+    #[cfg(target_family = "unix")]
+    #[clap(name = "tui")]
+    Tui(tui::TuiCommand),
+
+    /// Check the tree for dangling links.
+    #[clap(name = "fsck")]
+    Fsck(fsck::FsckCommand),
+
     #[clap(subcommand)]
     #[clap(name = "area")]
     Areas(AreaCmd),
@@ -168,6 +317,14 @@ enum Cmd {
     #[clap(subcommand)]
     #[clap(name = "item")]
     Item(ItemCmd),
+
+    #[clap(subcommand)]
+    #[clap(name = "store")]
+    Store(StoreCmd),
+
+    #[clap(subcommand)]
+    #[clap(name = "config")]
+    Config(ConfigCmd),
 }
 
 impl JCommand for Cmd {
@@ -176,10 +333,22 @@ impl JCommand for Cmd {
             Cmd::Init(cmd) => cmd.run(jd),
             Cmd::List(cmd) => cmd.run(jd),
             Cmd::Open(cmd) => cmd.run(jd),
+            Cmd::Pick(cmd) => cmd.run(jd),
+            Cmd::Recent(cmd) => cmd.run(jd),
+            Cmd::Jump(cmd) => cmd.run(jd),
+            Cmd::Cd(cmd) => cmd.run(jd),
+            Cmd::ShellInit(cmd) => cmd.run(jd),
+            Cmd::Completions(cmd) => cmd.run(jd),
+            Cmd::Complete(cmd) => cmd.run(jd),
+            #[cfg(target_family = "unix")]
+            Cmd::Tui(cmd) => cmd.run(jd),
             Cmd::Search(cmd) => cmd.run(jd),
+            Cmd::Fsck(cmd) => cmd.run(jd),
             Cmd::Areas(cmd) => cmd.run(jd),
             Cmd::Categories(cmd) => cmd.run(jd),
             Cmd::Item(cmd) => cmd.run(jd),
+            Cmd::Store(cmd) => cmd.run(jd),
+            Cmd::Config(cmd) => cmd.run(jd),
         }
     }
 
@@ -188,10 +357,22 @@ impl JCommand for Cmd {
             Cmd::Init(cmd) => cmd.run_json(jd),
             Cmd::List(cmd) => cmd.run_json(jd),
             Cmd::Open(cmd) => cmd.run_json(jd),
+            Cmd::Pick(cmd) => cmd.run_json(jd),
+            Cmd::Recent(cmd) => cmd.run_json(jd),
+            Cmd::Jump(cmd) => cmd.run_json(jd),
+            Cmd::Cd(cmd) => cmd.run_json(jd),
+            Cmd::ShellInit(cmd) => cmd.run_json(jd),
+            Cmd::Completions(cmd) => cmd.run_json(jd),
+            Cmd::Complete(cmd) => cmd.run_json(jd),
+            #[cfg(target_family = "unix")]
+            Cmd::Tui(cmd) => cmd.run_json(jd),
             Cmd::Search(cmd) => cmd.run_json(jd),
+            Cmd::Fsck(cmd) => cmd.run_json(jd),
             Cmd::Areas(cmd) => cmd.run_json(jd),
             Cmd::Categories(cmd) => cmd.run_json(jd),
             Cmd::Item(cmd) => cmd.run_json(jd),
+            Cmd::Store(cmd) => cmd.run_json(jd),
+            Cmd::Config(cmd) => cmd.run_json(jd),
         }
     }
 }
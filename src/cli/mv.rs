@@ -1,9 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
 use clap::Parser;
 
-use johnny::{JohnnyDecimal, ID};
+use johnny::{Item, JohnnyDecimal, ID};
 
 use super::JCommand;
 
@@ -15,10 +15,22 @@ pub struct MoveCommand {
     #[clap(long = "id")]
     id: Option<ID>,
 
+    /// Link the files in place instead of moving them.
+    #[clap(long = "link")]
+    link: bool,
+
     files: Vec<PathBuf>,
 }
 
 impl MoveCommand {
+    fn file(&self, jd: &mut JohnnyDecimal, f: &Path) -> Result<Item> {
+        if self.link {
+            jd.link(self.category, f, self.id.as_ref())
+        } else {
+            jd.mv(self.category, f, self.id.as_ref())
+        }
+    }
+
     fn validate_id(&self) -> Result<()> {
         if self.files.len() > 1 && self.id.is_some() {
             bail!("cannot specify an ID when uploading more than one file")
@@ -31,7 +43,7 @@ impl JCommand for MoveCommand {
     fn run(&self, mut jd: JohnnyDecimal) -> Result<()> {
         self.validate_id()?;
         for f in self.files.iter() {
-            let item = jd.mv(self.category, f, self.id.as_ref())?;
+            let item = self.file(&mut jd, f)?;
             println!("{}", item);
         }
         Ok(())
@@ -40,7 +52,7 @@ impl JCommand for MoveCommand {
     fn run_json(&self, mut jd: JohnnyDecimal) -> Result<()> {
         self.validate_id()?;
         for f in self.files.iter() {
-            jd.mv(self.category, f, self.id.as_ref())?;
+            self.file(&mut jd, f)?;
         }
         Ok(())
     }
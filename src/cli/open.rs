@@ -1,3 +1,5 @@
+use std::io;
+
 use anyhow::Result;
 use clap::Parser;
 
@@ -10,17 +12,33 @@ pub struct OpenCommand {
     id: String,
 }
 
-impl OpenCommand {
-    pub fn open(&self, jd: JohnnyDecimal) -> Result<()> {
-        let id = self.id.parse::<ID>()?;
-        if let Some(location) = jd.locate(&id)? {
-            match location {
-                Location::Path(p) => open::that(p)?,
-                Location::URL(url) => open::that(url)?,
+/// Opens the item with the default application, storing it back once done if needed.
+pub fn open_item(jd: &JohnnyDecimal, id: &ID) -> Result<()> {
+    if let Some(checkout) = jd.checkout(id)? {
+        let opened = match &checkout.location {
+            Location::Path(p) => open::that(p),
+            Location::URL(url) => open::that(url),
+        };
+
+        if checkout.needs_checkin {
+            if opened.is_ok() {
+                eprint!("Press enter once done with {} to store it back...", id);
+                io::stdin().read_line(&mut String::new())?;
             }
+            jd.checkin(id, checkout)?;
         }
 
-        Ok(())
+        opened?;
+        super::record_use(jd, id);
+    }
+
+    Ok(())
+}
+
+impl OpenCommand {
+    pub fn open(&self, jd: JohnnyDecimal) -> Result<()> {
+        let id = self.id.parse::<ID>()?;
+        open_item(&jd, &id)
     }
 }
 
@@ -0,0 +1,123 @@
+use std::io;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+
+use johnny::{Item, JohnnyDecimal, Location, ID};
+
+use super::{json, open, JCommand};
+
+/// Lists the items, or only the ones of a category.
+fn list_items(jd: &JohnnyDecimal, category: Option<usize>) -> Vec<Item> {
+    jd.index
+        .list_areas()
+        .into_iter()
+        .flat_map(|a| a.list_categories())
+        .filter(|c| category.map(|id| id == c.id).unwrap_or(true))
+        .flat_map(|c| c.list_items())
+        .collect()
+}
+
+/// Keeps a field on its line and column, whatever the name holds.
+fn field(s: &str) -> String {
+    s.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Where the item can be read in place, `None` when it would have to be fetched or unpacked.
+/// Listing never fails on an item, since `locate` is left to the selected one.
+pub fn listed_location(jd: &JohnnyDecimal, id: &ID) -> Option<Location> {
+    jd.local_path(id).ok().flatten().map(Location::Path)
+}
+
+/// Prints one `AC.ID<TAB>Name<TAB>Location` line per item, the location being empty when unknown.
+/// This format is read back by `jd pick --select` and must stay stable.
+pub fn print_flat(jd: &JohnnyDecimal, category: Option<usize>) -> Result<()> {
+    for item in list_items(jd, category) {
+        let location = listed_location(jd, &item.id)
+            .map(|l| l.to_string())
+            .unwrap_or_default();
+        println!("{}\t{}\t{}", item.id, field(&item.name), field(&location));
+    }
+    Ok(())
+}
+
+pub fn print_flat_json(jd: &JohnnyDecimal, category: Option<usize>) -> Result<()> {
+    let viewer = json::Viewer::new(jd);
+    let views = list_items(jd, category)
+        .iter()
+        .map(|item| viewer.flat_item(item))
+        .collect::<Result<Vec<_>>>()?;
+
+    println!("{}", serde_json::to_string(&views)?);
+    Ok(())
+}
+
+#[derive(Parser)]
+pub struct PickCommand {
+    /// Read a line printed by `jd pick` from stdin and open its item.
+    #[clap(long = "select")]
+    select: bool,
+
+    /// With `--select`, print the location of the item instead of opening it.
+    #[clap(long = "locate", requires = "select")]
+    locate: bool,
+}
+
+impl PickCommand {
+    /// Reads the chosen line, `None` if the picker was dismissed without a choice.
+    fn selection(&self) -> Result<Option<ID>> {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+
+        let code = line.split('\t').next().unwrap_or_default().trim();
+        if code.is_empty() {
+            return Ok(None);
+        }
+
+        let id = code
+            .parse::<ID>()
+            .map_err(|_| anyhow!("invalid selection '{}', expected a line of jd pick", code))?;
+        Ok(Some(id))
+    }
+
+    fn select(&self, jd: &JohnnyDecimal, json: bool) -> Result<()> {
+        let id = match self.selection()? {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        if !self.locate {
+            return open::open_item(jd, &id);
+        }
+
+        let location = jd
+            .locate(&id)?
+            .ok_or_else(|| anyhow!("{} has no location", id))?;
+        super::record_use(jd, &id);
+
+        if json {
+            println!("{}", serde_json::to_string(&location)?);
+        } else {
+            println!("{}", location);
+        }
+        Ok(())
+    }
+}
+
+impl JCommand for PickCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        if self.select {
+            self.select(&jd, false)
+        } else {
+            print_flat(&jd, None)
+        }
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        if self.select {
+            self.select(&jd, true)
+        } else {
+            print_flat_json(&jd, None)
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::Parser;
+
+use johnny::JohnnyDecimal;
+
+use serde::Serialize;
+
+use super::{json, JCommand};
+
+#[derive(Serialize)]
+struct RecentView {
+    #[serde(flatten)]
+    item: json::ItemView,
+    uses: u64,
+    last_used: u64,
+}
+
+/// Formats an age in seconds, e.g. `3h ago`.
+fn ago(elapsed: u64) -> String {
+    match elapsed {
+        e if e < 60 => String::from("just now"),
+        e if e < 60 * 60 => format!("{}m ago", e / 60),
+        e if e < 24 * 60 * 60 => format!("{}h ago", e / (60 * 60)),
+        e => format!("{}d ago", e / (24 * 60 * 60)),
+    }
+}
+
+#[derive(Parser)]
+pub struct RecentCommand {
+    /// How many items to list.
+    #[clap(long = "limit", short = 'n', default_value = "10")]
+    limit: usize,
+}
+
+impl JCommand for RecentCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        for (item, usage) in jd.recent()?.into_iter().take(self.limit) {
+            bunt::println!(
+                "{} {[dimmed]}",
+                item,
+                format!("({} uses, {})", usage.uses, ago(usage.age()?))
+            );
+        }
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        let viewer = json::Viewer::new(&jd);
+
+        let views = jd
+            .recent()?
+            .into_iter()
+            .take(self.limit)
+            .map(|(item, usage)| {
+                Ok(RecentView {
+                    item: viewer.item(&item)?,
+                    uses: usage.uses,
+                    last_used: usage.last_used,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        println!("{}", serde_json::to_string(&views)?);
+        Ok(())
+    }
+}
@@ -1,13 +1,20 @@
 use anyhow::{anyhow, Result};
 use clap::Parser;
 
-use johnny::JohnnyDecimal;
+use johnny::{Area, ContentHit, HitKind, HitLocation, Item, JohnnyDecimal, Query, SearchHit};
 
 use super::{json, JCommand};
 
 #[derive(Parser)]
 pub struct SearchCommand {
-    /// The string to search for.
+    /// The query, e.g. `area:10-19 name:~invoice -draft`.
+    ///
+    /// Words are matched fuzzily against item names. Terms can also filter on
+    /// `area:10-19`, `cat:11` (or `cat:11-13`), `id:11.001` and `name:text`,
+    /// with `name:~text` matching fuzzily. `after:2024-01-01` and `before:2024-01-01`
+    /// keep items by when their content was last modified. A leading `-` negates a term.
+    /// With `--regex` or `--glob`, the whole query is a single pattern.
+    #[clap(allow_hyphen_values = true)]
     expr: String,
 
     /// An optional area restriction.
@@ -17,58 +24,195 @@ pub struct SearchCommand {
     /// An optional category restriction.
     #[clap(long = "category", short = 'c')]
     category: Option<usize>,
+
+    /// Only return the N best matches.
+    #[clap(long = "limit", short = 'n')]
+    limit: Option<usize>,
+
+    /// Only return hits of these kinds: area, category or item.
+    #[clap(long = "kind", short = 'k', use_delimiter = true)]
+    kind: Vec<HitKind>,
+
+    /// Search the text of the files stored on disk instead of names.
+    #[clap(long = "content")]
+    content: bool,
+
+    /// Treat the query as a regex matched anywhere in names, ignoring case.
+    #[clap(long = "regex", conflicts_with_all = &["glob", "content"])]
+    regex: bool,
+
+    /// Treat the query as a glob matching whole names, ignoring case, e.g. `invoice*2024`.
+    #[clap(long = "glob", conflicts_with = "content")]
+    glob: bool,
 }
 
-impl JCommand for SearchCommand {
-    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
-        let mut last_area_name = String::default();
-        let mut last_category_name = String::default();
-
-        for result in jd.index.search(&self.expr) {
-            // TODO: This is super slow since we still search the full tree,
-            // implement it intelligently in the future please.
-            if let Some(a) = self.area.as_ref() {
-                if *a / 10 != result.id.category / 10 {
-                    continue;
-                }
+impl SearchCommand {
+    /// Whether the category passes the `--area` and `--category` restrictions.
+    fn in_scope(&self, category: usize) -> bool {
+        self.area.map(|a| a / 10 == category / 10).unwrap_or(true)
+            && self.category.map(|c| c == category).unwrap_or(true)
+    }
+
+    fn hits(&self, jd: &JohnnyDecimal) -> Result<Vec<SearchHit>> {
+        let mut hits = if self.regex || self.glob {
+            let mut hits = if self.regex {
+                jd.index.search_regex(&self.expr)?
+            } else {
+                jd.index.search_glob(&self.expr)?
+            };
+            hits.retain(|hit| match &hit.location {
+                HitLocation::Area(bounds) => self.category.is_none() && self.in_scope(bounds.0),
+                HitLocation::Category(category) => self.in_scope(*category),
+                HitLocation::Item(item) => self.in_scope(item.id.category),
+            });
+            hits
+        } else {
+            let mut query = self.expr.parse::<Query>()?;
+            if let Some(area) = self.area {
+                query = query.within_area(area);
             }
-            if let Some(c) = self.category.as_ref() {
-                if *c != result.id.category {
-                    continue;
-                }
+            if let Some(category) = self.category {
+                query = query.within_category(category);
             }
+            jd.search(&query)?
+        };
 
-            let area = jd
-                .index
-                .get_area_from_category(result.id.category)?
-                .ok_or_else(|| anyhow!("missing area"))?;
+        if !self.kind.is_empty() {
+            hits.retain(|hit| self.kind.contains(&hit.kind()));
+        }
+        if let Some(limit) = self.limit {
+            hits.truncate(limit);
+        }
 
-            let category = area
-                .get_category(result.id.category)?
-                .ok_or_else(|| anyhow!("missing category"))?;
+        Ok(hits)
+    }
 
-            if area.name != last_area_name {
-                bunt::println!("[{[blue + bold]:}]", area);
-                last_area_name = area.name.clone();
-            }
+    fn content_hits(&self, jd: &JohnnyDecimal) -> Result<Vec<ContentHit>> {
+        let content = jd.update_content_index()?;
+        for (path, reason) in content.skipped() {
+            bunt::eprintln!(
+                "{[yellow]} skipped {}: {}",
+                "warning:",
+                path.display(),
+                reason
+            );
+        }
+
+        let hits = content
+            .search(&self.expr)
+            .into_iter()
+            .filter(|hit| self.in_scope(hit.item.id.category));
+
+        Ok(match self.limit {
+            Some(limit) => hits.take(limit).collect(),
+            None => hits.collect(),
+        })
+    }
+}
 
-            if category.name != last_category_name {
-                bunt::println!("  {[green]:}", category);
-                last_category_name = category.name.clone();
+/// Prints hits as a tree, only repeating area and category headers when they change.
+#[derive(Default)]
+struct TreePrinter {
+    last_area: Option<String>,
+    last_category: Option<String>,
+    last_item: Option<String>,
+}
+
+impl TreePrinter {
+    fn area<'a>(&mut self, jd: &'a JohnnyDecimal, category: usize) -> Result<&'a Area> {
+        let area = jd
+            .index
+            .get_area_from_category(category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let header = format!("{}", area);
+        if self.last_area.as_ref() != Some(&header) {
+            bunt::println!("[{[blue + bold]:}]", header);
+            self.last_area = Some(header);
+            self.last_category = None;
+        }
+
+        Ok(area)
+    }
+
+    fn category(&mut self, jd: &JohnnyDecimal, category: usize) -> Result<()> {
+        let category = self
+            .area(jd, category)?
+            .get_category(category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        let header = format!("{}", category);
+        if self.last_category.as_ref() != Some(&header) {
+            bunt::println!("  {[green]:}", header);
+            self.last_category = Some(header);
+            self.last_item = None;
+        }
+
+        Ok(())
+    }
+
+    fn item(&mut self, jd: &JohnnyDecimal, item: &Item) -> Result<()> {
+        self.category(jd, item.id.category)?;
+
+        let line = format!("{}", item);
+        if self.last_item.as_ref() != Some(&line) {
+            println!("    {}", line);
+            self.last_item = Some(line);
+        }
+
+        Ok(())
+    }
+
+    fn hit(&mut self, jd: &JohnnyDecimal, hit: &SearchHit) -> Result<()> {
+        match &hit.location {
+            HitLocation::Area(bounds) => self.area(jd, bounds.0).map(|_| ()),
+            HitLocation::Category(category) => self.category(jd, *category),
+            HitLocation::Item(item) => self.item(jd, item),
+        }
+    }
+}
+
+impl JCommand for SearchCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        let mut printer = TreePrinter::default();
+
+        if self.content {
+            for hit in self.content_hits(&jd)? {
+                printer.item(&jd, &hit.item)?;
+                bunt::println!(
+                    "      {[dimmed]}:{[dimmed]}: {}",
+                    hit.path.display(),
+                    hit.line,
+                    hit.snippet
+                );
             }
+            return Ok(());
+        }
 
-            println!("    {}", result);
+        for hit in self.hits(&jd)? {
+            printer.hit(&jd, &hit)?;
         }
         Ok(())
     }
 
     fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
-        let hits = jd.index.search(&self.expr);
         let viewer = json::Viewer::new(&jd);
 
+        if self.content {
+            let views = self
+                .content_hits(&jd)?
+                .iter()
+                .map(|x| viewer.content_hit(x))
+                .collect::<Result<Vec<_>>>()?;
+            println!("{}", serde_json::to_string(&views)?);
+            return Ok(());
+        }
+
+        let hits = self.hits(&jd)?;
+
         let views = hits
             .iter()
-            .map(|x| viewer.item(x))
+            .map(|x| viewer.hit(x))
             .collect::<Result<Vec<_>>>()?;
 
         println!("{}", serde_json::to_string(&views)?);
@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use johnny::JohnnyDecimal;
+
+use super::JCommand;
+
+/// Wraps `jd` so `jd cd` changes the directory of the calling shell, works for bash and zsh.
+const POSIX_INIT: &str = r#"jd() {
+    if [ "$1" = "cd" ]; then
+        shift
+        local dir
+        dir="$(command jd cd "$@")" && [ -n "$dir" ] && builtin cd -- "$dir"
+    else
+        command jd "$@"
+    fi
+}
+
+jdc() {
+    jd cd "$@"
+}
+"#;
+
+const FISH_INIT: &str = r#"function jd --wraps jd
+    if test "$argv[1]" = cd
+        set -e argv[1]
+        set -l dir (command jd cd $argv); or return
+        test -n "$dir"; and builtin cd -- $dir
+    else
+        command jd $argv
+    end
+end
+
+alias jdc 'jd cd'
+"#;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => bail!("unsupported shell: {}, expected bash, zsh or fish", s),
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct ShellInitCommand {
+    /// The shell to integrate with: bash, zsh or fish.
+    shell: Shell,
+}
+
+impl ShellInitCommand {
+    /// Prints the script to source from the shell's startup file.
+    pub fn print(&self) -> Result<()> {
+        match self.shell {
+            Shell::Bash | Shell::Zsh => print!("{}", POSIX_INIT),
+            Shell::Fish => print!("{}", FISH_INIT),
+        }
+        Ok(())
+    }
+}
+
+impl JCommand for ShellInitCommand {
+    fn run(&self, _jd: JohnnyDecimal) -> Result<()> {
+        self.print()
+    }
+
+    fn run_json(&self, _jd: JohnnyDecimal) -> Result<()> {
+        self.print()
+    }
+}
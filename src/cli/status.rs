@@ -0,0 +1,109 @@
+use anyhow::Result;
+use clap::Parser;
+
+use johnny::{Item, ItemStatus, JohnnyDecimal, ID};
+
+use serde::Serialize;
+
+use super::JCommand;
+
+#[derive(Serialize)]
+struct StatusView {
+    id: String,
+    name: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    status: Option<ItemStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct StatusCommand {
+    /// The AC.ID code to check, every item is checked when omitted.
+    id: Option<ID>,
+
+    /// An optional category restriction.
+    #[clap(long = "category", short = 'c')]
+    category: Option<usize>,
+}
+
+impl StatusCommand {
+    /// Checks every selected item, an item that can't be checked (e.g. its category has no
+    /// resolver) is reported with its error instead of aborting the whole listing.
+    fn statuses(&self, jd: &JohnnyDecimal) -> Result<Vec<(Item, Result<ItemStatus>)>> {
+        let mut items = Vec::new();
+        for area in jd.index.list_areas() {
+            for category in area.list_categories() {
+                if let Some(c) = self.category {
+                    if c != category.id {
+                        continue;
+                    }
+                }
+
+                for item in category.list_items() {
+                    if let Some(id) = self.id.as_ref() {
+                        if id.category != item.id.category || id.id != item.id.id {
+                            continue;
+                        }
+                    }
+                    items.push(item);
+                }
+            }
+        }
+
+        let mut statuses = Vec::new();
+        for item in items {
+            match jd.status(&item.id) {
+                Ok(Some(status)) => statuses.push((item, Ok(status))),
+                Ok(None) => {}
+                Err(e) => statuses.push((item, Err(e))),
+            }
+        }
+
+        Ok(statuses)
+    }
+}
+
+impl JCommand for StatusCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        for (item, status) in self.statuses(&jd)? {
+            let status = match status {
+                Ok(status) => status,
+                Err(e) => {
+                    bunt::println!("{} [{[red]}]", item, e);
+                    continue;
+                }
+            };
+
+            let branch = status.branch.unwrap_or_else(|| String::from("(detached)"));
+            if status.dirty {
+                bunt::println!("{} [{[cyan]}] {[yellow]}", item, branch, "*");
+            } else {
+                bunt::println!("{} [{[cyan]}]", item, branch);
+            }
+        }
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        let views = self
+            .statuses(&jd)?
+            .into_iter()
+            .map(|(item, status)| {
+                let (status, error) = match status {
+                    Ok(status) => (Some(status), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                StatusView {
+                    id: format!("{}", item.id),
+                    name: item.name,
+                    status,
+                    error,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        println!("{}", serde_json::to_string(&views)?);
+        Ok(())
+    }
+}
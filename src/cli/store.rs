@@ -0,0 +1,45 @@
+use anyhow::Result;
+use clap::Parser;
+
+use johnny::JohnnyDecimal;
+
+use super::JCommand;
+
+#[derive(Parser)]
+pub struct GcCommand {}
+
+impl JCommand for GcCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        let removed = jd.gc()?;
+        for blob in removed.iter() {
+            println!("{}", blob.to_string_lossy());
+        }
+        bunt::println!("removed {[bold]} unreferenced blob(s)", removed.len());
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        println!("{}", serde_json::to_string(&jd.gc()?)?);
+        Ok(())
+    }
+}
+
+#[derive(Parser)]
+pub struct DupesCommand {}
+
+impl JCommand for DupesCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        for duplicate in jd.duplicates()? {
+            bunt::println!("{[yellow]}", duplicate.hash);
+            for file in duplicate.files {
+                println!("  {}", file.to_string_lossy());
+            }
+        }
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        println!("{}", serde_json::to_string(&jd.duplicates()?)?);
+        Ok(())
+    }
+}
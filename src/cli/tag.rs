@@ -0,0 +1,45 @@
+use anyhow::Result;
+use clap::Parser;
+
+use johnny::{JohnnyDecimal, ID};
+
+use super::JCommand;
+
+#[derive(Parser)]
+pub struct TagCommand {
+    id: ID,
+
+    /// Tags to add, or to remove with `--remove`. The item's tags are listed when omitted.
+    tags: Vec<String>,
+
+    #[clap(long = "remove", short = 'r')]
+    remove: bool,
+}
+
+impl TagCommand {
+    fn apply(&self, jd: &JohnnyDecimal) -> Result<Vec<String>> {
+        let mut tags = jd.tags()?.get(&self.id);
+        for tag in self.tags.iter() {
+            tags = if self.remove {
+                jd.untag(&self.id, tag)?
+            } else {
+                jd.tag(&self.id, tag)?
+            };
+        }
+        Ok(tags)
+    }
+}
+
+impl JCommand for TagCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        for tag in self.apply(&jd)? {
+            println!("{}", tag);
+        }
+        Ok(())
+    }
+
+    fn run_json(&self, jd: JohnnyDecimal) -> Result<()> {
+        println!("{}", serde_json::to_string(&self.apply(&jd)?)?);
+        Ok(())
+    }
+}
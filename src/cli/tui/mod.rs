@@ -0,0 +1,648 @@
+mod term;
+
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, ensure, Result};
+use clap::Parser;
+
+use johnny::{fuzzy_score, HitLocation, Item, JohnnyDecimal, Location, ID};
+
+use self::term::{Key, Terminal};
+use super::{open, JCommand};
+
+const HELP: &str =
+    "↑↓ select  ←→ pane  / filter  enter/o open  r rename  m move  n new  d delete  q quit";
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const REVERSE: &str = "\x1b[7m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const BLUE: &str = "\x1b[34m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pane {
+    Areas = 0,
+    Categories = 1,
+    Items = 2,
+}
+
+impl Pane {
+    fn title(self) -> &'static str {
+        match self {
+            Pane::Areas => "Areas",
+            Pane::Categories => "Categories",
+            Pane::Items => "Items",
+        }
+    }
+
+    fn left(self) -> Self {
+        match self {
+            Pane::Items => Pane::Categories,
+            _ => Pane::Areas,
+        }
+    }
+
+    fn right(self) -> Self {
+        match self {
+            Pane::Areas => Pane::Categories,
+            _ => Pane::Items,
+        }
+    }
+}
+
+/// An action waiting for the user to type its argument.
+#[derive(Clone, Debug)]
+enum Action {
+    Rename(ID),
+    Move(ID),
+    New(usize),
+}
+
+#[derive(Clone, Debug)]
+enum Mode {
+    Browse,
+
+    /// Typing the filter of the focused pane.
+    Filter,
+    Prompt(Action, String),
+
+    /// Waiting for the user to confirm moving the item to the trash.
+    Delete(ID),
+}
+
+/// A row of a pane.
+#[derive(Clone, Debug)]
+struct Node {
+    location: HitLocation,
+    label: String,
+    name: String,
+}
+
+/// Keeps the nodes matching the filter, best matches first.
+fn filter(nodes: Vec<Node>, filter: &str) -> Vec<Node> {
+    if filter.is_empty() {
+        return nodes;
+    }
+
+    let mut scored = nodes
+        .into_iter()
+        .filter_map(|n| fuzzy_score(filter, &n.name).map(|score| (score, n)))
+        .collect::<Vec<_>>();
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+    scored.into_iter().map(|(_, n)| n).collect()
+}
+
+/// Cuts or pads the text to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+    if count > width {
+        let mut cut = text
+            .chars()
+            .take(width.saturating_sub(1))
+            .collect::<String>();
+        if width > 0 {
+            cut.push('…');
+        }
+        cut
+    } else {
+        format!("{}{}", text, " ".repeat(width - count))
+    }
+}
+
+/// Lists a folder for the preview, folders first.
+fn list_folder(path: &Path) -> Result<Vec<String>> {
+    let mut entries = fs::read_dir(path)?
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            if e.path().is_dir() {
+                (false, format!("{}/", name))
+            } else {
+                (true, name)
+            }
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    if entries.is_empty() {
+        return Ok(vec![String::from("(empty)")]);
+    }
+    Ok(entries.into_iter().map(|(_, name)| name).collect())
+}
+
+struct App {
+    jd: JohnnyDecimal,
+    pane: Pane,
+    selected: [usize; 3],
+    filters: [String; 3],
+    mode: Mode,
+
+    /// Outcome of the last action, and whether it failed.
+    status: Option<(String, bool)>,
+
+    /// The preview of the selected node, by label, so folders aren't read on every frame.
+    preview: Option<(String, Vec<String>)>,
+}
+
+impl App {
+    fn new(jd: JohnnyDecimal) -> Self {
+        Self {
+            jd,
+            pane: Pane::Areas,
+            selected: [0; 3],
+            filters: Default::default(),
+            mode: Mode::Browse,
+            status: None,
+            preview: None,
+        }
+    }
+
+    fn nodes(&self, pane: Pane) -> Vec<Node> {
+        let nodes = match pane {
+            Pane::Areas => self
+                .jd
+                .index
+                .list_areas()
+                .into_iter()
+                .map(|a| Node {
+                    location: HitLocation::Area(a.bounds),
+                    label: format!("{}", a),
+                    name: a.name.clone(),
+                })
+                .collect(),
+            Pane::Categories => match self.selected_node(Pane::Areas) {
+                Some(Node {
+                    location: HitLocation::Area(bounds),
+                    ..
+                }) => self
+                    .jd
+                    .index
+                    .get_area(bounds)
+                    .ok()
+                    .flatten()
+                    .map(|a| a.list_categories())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| Node {
+                        location: HitLocation::Category(c.id),
+                        label: format!("{}", c),
+                        name: c.name.clone(),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Pane::Items => match self.selected_node(Pane::Categories) {
+                Some(Node {
+                    location: HitLocation::Category(category),
+                    ..
+                }) => self
+                    .jd
+                    .index
+                    .get_area_from_category(category)
+                    .ok()
+                    .flatten()
+                    .and_then(|a| a.get_category(category).ok().flatten())
+                    .map(|c| c.list_items())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|i| Node {
+                        label: format!("{}", i),
+                        name: i.name.clone(),
+                        location: HitLocation::Item(i),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+        };
+
+        filter(nodes, &self.filters[pane as usize])
+    }
+
+    fn selected_node(&self, pane: Pane) -> Option<Node> {
+        let nodes = self.nodes(pane);
+        let last = nodes.len().checked_sub(1)?;
+        nodes
+            .into_iter()
+            .nth(self.selected[pane as usize].min(last))
+    }
+
+    /// The item acted upon, only when the items pane is focused.
+    fn selected_item(&self) -> Option<Item> {
+        match self.selected_node(Pane::Items)?.location {
+            HitLocation::Item(item) if self.pane == Pane::Items => Some(item),
+            _ => None,
+        }
+    }
+
+    /// The category new items go to, the one selected or holding the selected item.
+    fn selected_category(&self) -> Option<usize> {
+        if self.pane == Pane::Areas {
+            return None;
+        }
+        match self.selected_node(Pane::Categories)?.location {
+            HitLocation::Category(category) => Some(category),
+            _ => None,
+        }
+    }
+
+    /// Moves the selection of the focused pane, resetting the panes on its right.
+    fn select(&mut self, position: usize) {
+        let count = self.nodes(self.pane).len();
+        let position = position.min(count.saturating_sub(1));
+        if position == self.selected[self.pane as usize] {
+            return;
+        }
+
+        self.selected[self.pane as usize] = position;
+        self.reset_right();
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        self.filters[self.pane as usize] = filter;
+        self.selected[self.pane as usize] = 0;
+        self.reset_right();
+    }
+
+    /// Clears the panes on the right of the focused one, as they show what it selects.
+    fn reset_right(&mut self) {
+        for pane in (self.pane as usize + 1)..3 {
+            self.selected[pane] = 0;
+            self.filters[pane].clear();
+        }
+    }
+
+    fn report(&mut self, result: Result<String>) {
+        self.status = Some(match result {
+            Ok(message) => (message, false),
+            Err(e) => (e.to_string(), true),
+        });
+        self.preview = None;
+    }
+
+    fn open(&mut self, id: &ID, terminal: &mut Terminal) -> Result<String> {
+        // Opening can wait on the user to store the item back, which needs the regular terminal.
+        terminal.suspend()?;
+        let opened = open::open_item(&self.jd, id);
+        terminal.resume()?;
+
+        opened?;
+        Ok(format!("Opened {}", id))
+    }
+
+    fn run_action(&mut self, action: Action, input: &str) -> Result<String> {
+        let input = input.trim();
+        ensure!(!input.is_empty(), "nothing was typed");
+
+        match action {
+            Action::Rename(id) => {
+                let item = self.jd.rename(id, input)?;
+                Ok(format!("Renamed to {}", item))
+            }
+            Action::Move(id) => {
+                let category = input
+                    .parse::<usize>()
+                    .map_err(|_| anyhow!("expected a category like 11"))?;
+                let item = self.jd.relocate(&id, category)?;
+                Ok(format!("Moved to {}", item))
+            }
+            Action::New(category) => {
+                ensure!(!input.contains('/'), "names can't contain '/'");
+
+                // Items are filed from a path, start from an empty folder.
+                let scratch = std::env::temp_dir().join(format!("jd-new-{}", std::process::id()));
+                let folder = scratch.join(input);
+                fs::create_dir_all(&folder)?;
+                let created = self.jd.mv(category, &folder, None);
+                fs::remove_dir_all(&scratch)?;
+
+                Ok(format!("Created {}", created?))
+            }
+        }
+    }
+
+    fn trash(&mut self, id: &ID) -> Result<String> {
+        let dir = self
+            .jd
+            .trash(id)?
+            .ok_or_else(|| anyhow!("{} doesn't exist", id))?;
+        Ok(format!("Moved {} to {}", id, dir.to_string_lossy()))
+    }
+
+    /// Handles a key, returning false once the user quits.
+    fn handle(&mut self, key: Key, terminal: &mut Terminal) -> Result<bool> {
+        match self.mode.clone() {
+            Mode::Filter => {
+                let mut filter = self.filters[self.pane as usize].clone();
+                match key {
+                    Key::Enter => self.mode = Mode::Browse,
+                    Key::Esc => {
+                        self.set_filter(String::new());
+                        self.mode = Mode::Browse;
+                    }
+                    Key::Backspace => {
+                        filter.pop();
+                        self.set_filter(filter);
+                    }
+                    Key::Char(c) => {
+                        filter.push(c);
+                        self.set_filter(filter);
+                    }
+                    Key::Up => self.select(self.selected[self.pane as usize].saturating_sub(1)),
+                    Key::Down => self.select(self.selected[self.pane as usize] + 1),
+                    _ => {}
+                }
+            }
+            Mode::Prompt(action, mut input) => match key {
+                Key::Enter => {
+                    self.mode = Mode::Browse;
+                    let result = self.run_action(action, &input);
+                    self.report(result);
+                }
+                Key::Esc => self.mode = Mode::Browse,
+                Key::Backspace => {
+                    input.pop();
+                    self.mode = Mode::Prompt(action, input);
+                }
+                Key::Char(c) => {
+                    input.push(c);
+                    self.mode = Mode::Prompt(action, input);
+                }
+                _ => {}
+            },
+            Mode::Delete(id) => {
+                self.mode = Mode::Browse;
+                if key == Key::Char('y') || key == Key::Char('Y') {
+                    let result = self.trash(&id);
+                    self.report(result);
+                }
+            }
+            Mode::Browse => return self.browse(key, terminal),
+        }
+
+        Ok(true)
+    }
+
+    fn browse(&mut self, key: Key, terminal: &mut Terminal) -> Result<bool> {
+        let selected = self.selected[self.pane as usize];
+        self.status = None;
+
+        match key {
+            Key::Char('q') | Key::Ctrl('c') => return Ok(false),
+            Key::Esc if self.filters[self.pane as usize].is_empty() => return Ok(false),
+            Key::Esc => self.set_filter(String::new()),
+            Key::Up | Key::Char('k') => self.select(selected.saturating_sub(1)),
+            Key::Down | Key::Char('j') => self.select(selected + 1),
+            Key::PageUp => self.select(selected.saturating_sub(10)),
+            Key::PageDown => self.select(selected + 10),
+            Key::Home | Key::Char('g') => self.select(0),
+            Key::End | Key::Char('G') => self.select(usize::MAX),
+            Key::Left | Key::Char('h') | Key::BackTab => self.pane = self.pane.left(),
+            Key::Right | Key::Char('l') | Key::Tab if self.selected_node(self.pane).is_some() => {
+                self.pane = self.pane.right()
+            }
+            Key::Char('/') => self.mode = Mode::Filter,
+            Key::Enter | Key::Char('o') => {
+                if let Some(item) = self.selected_item() {
+                    let result = self.open(&item.id, terminal);
+                    self.report(result);
+                } else if key == Key::Enter && self.selected_node(self.pane).is_some() {
+                    self.pane = self.pane.right();
+                }
+            }
+            Key::Char('r') => {
+                if let Some(item) = self.selected_item() {
+                    self.mode = Mode::Prompt(Action::Rename(item.id), item.name);
+                }
+            }
+            Key::Char('m') => {
+                if let Some(item) = self.selected_item() {
+                    self.mode = Mode::Prompt(Action::Move(item.id), String::new());
+                }
+            }
+            Key::Char('n') => {
+                if let Some(category) = self.selected_category() {
+                    self.mode = Mode::Prompt(Action::New(category), String::new());
+                }
+            }
+            Key::Char('d') => {
+                if let Some(item) = self.selected_item() {
+                    self.mode = Mode::Delete(item.id);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    /// Lists what the focused selection holds on disk.
+    fn preview(&mut self) -> Vec<String> {
+        let node = match self.selected_node(self.pane) {
+            Some(node) => node,
+            None => return Vec::new(),
+        };
+
+        if let Some((label, lines)) = &self.preview {
+            if *label == node.label {
+                return lines.clone();
+            }
+        }
+
+        let folder = |path: Option<PathBuf>| -> Result<Vec<String>> {
+            match path {
+                Some(path) => {
+                    let mut lines = vec![path.to_string_lossy().to_string()];
+                    lines.append(&mut list_folder(&path)?);
+                    Ok(lines)
+                }
+                None => Ok(vec![String::from("(not on disk)")]),
+            }
+        };
+
+        let lines = match &node.location {
+            HitLocation::Area(bounds) => self.jd.locate_area(*bounds).and_then(folder),
+            HitLocation::Category(category) => self.jd.locate_category(*category).and_then(folder),
+            HitLocation::Item(item) => match self.jd.locate(&item.id) {
+                Ok(Some(Location::Path(p))) if p.is_dir() => folder(Some(p)),
+                Ok(Some(Location::Path(p))) if p.exists() => Ok(vec![
+                    p.to_string_lossy().to_string(),
+                    format!("{} bytes", fs::metadata(&p).map(|m| m.len()).unwrap_or(0)),
+                ]),
+                Ok(Some(Location::URL(url))) => Ok(vec![url]),
+                Ok(_) => folder(None),
+                Err(e) => Err(e),
+            },
+        };
+
+        let lines = lines.unwrap_or_else(|e| vec![format!("error: {}", e)]);
+        self.preview = Some((node.label, lines.clone()));
+        lines
+    }
+
+    fn pane_lines(&self, pane: Pane, width: usize, height: usize) -> Vec<String> {
+        let focused = pane == self.pane;
+        let nodes = self.nodes(pane);
+        let selected = self.selected[pane as usize].min(nodes.len().saturating_sub(1));
+
+        let filter = &self.filters[pane as usize];
+        let title = if filter.is_empty() {
+            String::from(pane.title())
+        } else {
+            format!("{} /{}", pane.title(), filter)
+        };
+
+        let mut lines = vec![if focused {
+            format!("{}{}{}", REVERSE, fit(&title, width), RESET)
+        } else {
+            format!("{}{}{}", BOLD, fit(&title, width), RESET)
+        }];
+
+        let rows = height.saturating_sub(1);
+        let offset = (selected + 1).saturating_sub(rows);
+        for (i, node) in nodes.iter().enumerate().skip(offset).take(rows) {
+            let color = match node.location {
+                HitLocation::Area(_) => BLUE,
+                HitLocation::Category(_) => GREEN,
+                HitLocation::Item(_) => "",
+            };
+            let style = match (i == selected, focused) {
+                (true, true) => REVERSE,
+                (true, false) => BOLD,
+                _ => "",
+            };
+            lines.push(format!(
+                "{}{}{}{}",
+                color,
+                style,
+                fit(&node.label, width),
+                RESET
+            ));
+        }
+
+        while lines.len() < height {
+            lines.push(" ".repeat(width));
+        }
+        lines
+    }
+
+    /// The line above the key help, with its style.
+    fn footer(&self) -> (String, &'static str) {
+        let line = match &self.mode {
+            Mode::Filter => format!("/{}", self.filters[self.pane as usize]),
+            Mode::Prompt(Action::Rename(id), input) => format!("Rename {} to: {}", id, input),
+            Mode::Prompt(Action::Move(id), input) => format!("Move {} to category: {}", id, input),
+            Mode::Prompt(Action::New(category), input) => {
+                format!("New item in {:02}: {}", category, input)
+            }
+            Mode::Delete(id) => format!("Move {} to the trash? [y/N]", id),
+            Mode::Browse => match &self.status {
+                Some((message, true)) => return (message.clone(), RED),
+                Some((message, false)) => message.clone(),
+                None => String::new(),
+            },
+        };
+        (line, "")
+    }
+
+    fn render(&mut self, (width, height): (usize, usize)) -> String {
+        let mut frame = String::from("\x1b[H");
+        let mut rows = Vec::new();
+
+        if width < 40 || height < 8 {
+            rows.push(fit("The terminal is too small.", width));
+        } else {
+            let body = height - 3;
+            let panes_height = (body * 2 / 3).max(4);
+            let preview_height = body - panes_height;
+
+            let side = width / 4;
+            let areas = self.pane_lines(Pane::Areas, side, panes_height);
+            let categories = self.pane_lines(Pane::Categories, side, panes_height);
+            let items = self.pane_lines(Pane::Items, width - 2 * side - 2, panes_height);
+
+            let breadcrumb = [Pane::Areas, Pane::Categories]
+                .iter()
+                .take(self.pane as usize)
+                .filter_map(|p| self.selected_node(*p).map(|n| n.label))
+                .collect::<Vec<_>>()
+                .join(" › ");
+            rows.push(format!(
+                "{}{}{}",
+                BOLD,
+                fit(&format!(" jd  {}", breadcrumb), width),
+                RESET
+            ));
+
+            for ((area, category), item) in areas.iter().zip(&categories).zip(&items) {
+                rows.push(format!(
+                    "{}{dim}│{reset}{}{dim}│{reset}{}",
+                    area,
+                    category,
+                    item,
+                    dim = DIM,
+                    reset = RESET
+                ));
+            }
+
+            rows.push(format!("{}{}{}", DIM, "─".repeat(width), RESET));
+            let preview = self.preview();
+            for row in 0..preview_height.saturating_sub(1) {
+                rows.push(fit(
+                    preview.get(row).map(|s| s.as_str()).unwrap_or(""),
+                    width,
+                ));
+            }
+
+            let (footer, style) = self.footer();
+            rows.push(format!("{}{}{}", style, fit(&footer, width), RESET));
+            rows.push(format!("{}{}{}", DIM, fit(HELP, width), RESET));
+        }
+
+        for (i, row) in rows.iter().enumerate() {
+            frame.push_str(&format!("\x1b[{};1H{}\x1b[K", i + 1, row));
+        }
+        frame.push_str("\x1b[J");
+        frame
+    }
+
+    fn run(&mut self) -> Result<()> {
+        let mut terminal = Terminal::new()?;
+        let mut size = (0, 0);
+        let mut dirty = true;
+
+        loop {
+            let current = terminal.size();
+            if dirty || current != size {
+                size = current;
+                let frame = self.render(size);
+                terminal.draw(&frame)?;
+                dirty = false;
+            }
+
+            for key in terminal.read_keys()? {
+                dirty = true;
+                if !self.handle(key, &mut terminal)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct TuiCommand {}
+
+impl JCommand for TuiCommand {
+    fn run(&self, jd: JohnnyDecimal) -> Result<()> {
+        App::new(jd).run()
+    }
+
+    fn run_json(&self, _jd: JohnnyDecimal) -> Result<()> {
+        bail!("the terminal interface has no JSON output");
+    }
+}
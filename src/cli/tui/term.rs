@@ -0,0 +1,176 @@
+use std::io::{self, Write};
+use std::os::unix::io::RawFd;
+
+use anyhow::{ensure, Result};
+
+use nix::errno::Errno;
+use nix::libc;
+use nix::sys::termios::{self, SetArg, SpecialCharacterIndices, Termios};
+use nix::unistd;
+
+const STDIN: RawFd = libc::STDIN_FILENO;
+const STDOUT: RawFd = libc::STDOUT_FILENO;
+
+/// How long a read waits for input, in tenths of a second, so resizes are noticed without a key press.
+const READ_TIMEOUT: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Esc,
+}
+
+/// Decodes the bytes of a single read, which can hold several keys when typing fast or pasting.
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut chars = text.chars().peekable();
+    let mut keys = Vec::new();
+
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' => match chars.peek() {
+                Some('[') | Some('O') => {
+                    chars.next();
+                    match chars.next() {
+                        Some('A') => Key::Up,
+                        Some('B') => Key::Down,
+                        Some('C') => Key::Right,
+                        Some('D') => Key::Left,
+                        Some('H') => Key::Home,
+                        Some('F') => Key::End,
+                        Some('Z') => Key::BackTab,
+                        // e.g. `5~` for page up, modifiers like `5;2~` are ignored.
+                        Some(d) if d.is_ascii_digit() => {
+                            let mut code = String::from(d);
+                            for c in chars.by_ref() {
+                                if !c.is_ascii_digit() {
+                                    break;
+                                }
+                                code.push(c);
+                            }
+                            match code.as_str() {
+                                "1" | "7" => Key::Home,
+                                "4" | "8" => Key::End,
+                                "5" => Key::PageUp,
+                                "6" => Key::PageDown,
+                                _ => continue,
+                            }
+                        }
+                        _ => continue,
+                    }
+                }
+                _ => Key::Esc,
+            },
+            '\r' | '\n' => Key::Enter,
+            '\t' => Key::Tab,
+            '\x7f' | '\x08' => Key::Backspace,
+            c if (c as u32) < 0x20 => Key::Ctrl((b'a' + c as u8 - 1) as char),
+            c => Key::Char(c),
+        };
+        keys.push(key);
+    }
+
+    keys
+}
+
+/// The terminal in raw mode on the alternate screen, restored when dropped.
+pub struct Terminal {
+    original: Termios,
+    active: bool,
+}
+
+impl Terminal {
+    pub fn new() -> Result<Self> {
+        ensure!(
+            unistd::isatty(STDIN)? && unistd::isatty(STDOUT)?,
+            "the terminal interface needs a terminal"
+        );
+
+        let mut terminal = Self {
+            original: termios::tcgetattr(STDIN)?,
+            active: false,
+        };
+        terminal.resume()?;
+        Ok(terminal)
+    }
+
+    /// Switches to raw mode on the alternate screen.
+    pub fn resume(&mut self) -> Result<()> {
+        let mut raw = self.original.clone();
+        termios::cfmakeraw(&mut raw);
+        raw.control_chars[SpecialCharacterIndices::VMIN as usize] = 0;
+        raw.control_chars[SpecialCharacterIndices::VTIME as usize] = READ_TIMEOUT;
+        termios::tcsetattr(STDIN, SetArg::TCSAFLUSH, &raw)?;
+
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        self.active = true;
+        Ok(())
+    }
+
+    /// Gives the terminal back as it was, e.g. while another program uses it.
+    pub fn suspend(&mut self) -> Result<()> {
+        print!("\x1b[2J\x1b[?25h\x1b[?1049l");
+        io::stdout().flush()?;
+        termios::tcsetattr(STDIN, SetArg::TCSAFLUSH, &self.original)?;
+        self.active = false;
+        Ok(())
+    }
+
+    /// The size of the terminal as `(columns, rows)`.
+    pub fn size(&self) -> (usize, usize) {
+        let mut size = libc::winsize {
+            ws_row: 0,
+            ws_col: 0,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+
+        let ok = unsafe { libc::ioctl(STDOUT, libc::TIOCGWINSZ, &mut size) } == 0;
+        if ok && size.ws_col > 0 && size.ws_row > 0 {
+            (size.ws_col as usize, size.ws_row as usize)
+        } else {
+            (80, 24)
+        }
+    }
+
+    /// Waits briefly for input, returning no keys if there was none.
+    pub fn read_keys(&self) -> Result<Vec<Key>> {
+        let mut buf = [0; 64];
+        let read = match unistd::read(STDIN, &mut buf) {
+            Ok(read) => read,
+            Err(nix::Error::Sys(Errno::EINTR)) => 0,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(parse_keys(&buf[..read]))
+    }
+
+    /// Writes a full frame at once.
+    pub fn draw(&self, frame: &str) -> Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.active {
+            let _ = self.suspend();
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+
+use crate::Index;
+
+/// Where the index is persisted.
+pub trait IndexBackend {
+    fn load(&self) -> Result<Index>;
+    fn save(&self, index: &Index) -> Result<()>;
+}
+
+/// Stores the index as a JSON file.
+pub struct FileIndexBackend {
+    path: PathBuf,
+}
+
+impl FileIndexBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl IndexBackend for FileIndexBackend {
+    fn load(&self) -> Result<Index> {
+        Index::load(&self.path)
+    }
+
+    fn save(&self, index: &Index) -> Result<()> {
+        index.save(&self.path)
+    }
+}
+
+/// Keeps the index in memory, clones share the same storage.
+#[derive(Clone, Default)]
+pub struct MemoryIndexBackend {
+    index: Arc<Mutex<Option<Index>>>,
+}
+
+impl MemoryIndexBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_index(index: Index) -> Self {
+        Self {
+            index: Arc::new(Mutex::new(Some(index))),
+        }
+    }
+
+    /// Returns the last saved index.
+    pub fn snapshot(&self) -> Option<Index> {
+        self.index.lock().ok().and_then(|i| i.clone())
+    }
+}
+
+impl IndexBackend for MemoryIndexBackend {
+    fn load(&self) -> Result<Index> {
+        self.snapshot().ok_or_else(|| anyhow!("no index saved"))
+    }
+
+    fn save(&self, index: &Index) -> Result<()> {
+        let mut guard = self
+            .index
+            .lock()
+            .map_err(|_| anyhow!("index backend poisoned"))?;
+        *guard = Some(index.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::config::{Resolver, ResolverConfig};
+use crate::resolver::{
+    ArchiveResolver, DiskResolver, ForgeResolver, GitResolver, GithubResolver, LinkResolver,
+    PluginResolver, ResolverChain, S3Resolver, StoreResolver, VaultResolver, WebDavResolver,
+};
+use crate::{
+    Config, FileIndexBackend, Index, IndexBackend, JohnnyDecimal, LocationResolver,
+    ResolverConstraint,
+};
+
+type ResolverFactory = Box<dyn Fn(Value) -> Result<Arc<dyn LocationResolver>>>;
+
+/// Builds a `JohnnyDecimal` client from a config, an index and custom resolvers.
+#[derive(Default)]
+pub struct JohnnyDecimalBuilder {
+    config: Option<Config>,
+    index: Option<Index>,
+    backend: Option<Box<dyn IndexBackend>>,
+    resolvers: Vec<(ResolverConstraint, Arc<dyn LocationResolver>)>,
+    kinds: HashMap<String, ResolverFactory>,
+    skip_validation: bool,
+}
+
+impl JohnnyDecimalBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the config, `Config::default()` is used if none is provided.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Sets the index, otherwise it is loaded from the index backend.
+    pub fn index(mut self, index: Index) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Sets where the index is persisted, defaults to the config's index path.
+    pub fn index_backend(mut self, backend: Box<dyn IndexBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Builds even when the config doesn't pass `Config::validate`, e.g. to report its problems.
+    pub fn skip_validation(mut self) -> Self {
+        self.skip_validation = true;
+        self
+    }
+
+    /// Registers a resolver for the categories matching the constraint.
+    /// Registered resolvers take precedence over the ones declared in the config.
+    pub fn resolver(
+        mut self,
+        constraint: ResolverConstraint,
+        resolver: Arc<dyn LocationResolver>,
+    ) -> Self {
+        self.resolvers.push((constraint, resolver));
+        self
+    }
+
+    /// Registers a factory for `ResolverConfig::Custom` entries of the given kind.
+    /// The entry's options are deserialized to `T` before being handed to the factory.
+    pub fn resolver_kind<T, F>(mut self, kind: &str, factory: F) -> Self
+    where
+        T: DeserializeOwned,
+        F: Fn(T) -> Result<Arc<dyn LocationResolver>> + 'static,
+    {
+        let kind_name = String::from(kind);
+        self.kinds.insert(
+            String::from(kind),
+            Box::new(move |options| {
+                let typed = serde_json::from_value(options).map_err(|e| {
+                    anyhow!("invalid options for resolver kind '{}': {}", kind_name, e)
+                })?;
+                factory(typed)
+            }),
+        );
+        self
+    }
+
+    // Resolvers are shared through `Arc` like everywhere else, even though chains aren't `Sync`.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn build_resolver(&self, resolver: &Resolver) -> Result<Arc<dyn LocationResolver>> {
+        let primary = self.build_config(&resolver.config, &resolver.constraint)?;
+        if resolver.fallbacks.is_empty() {
+            return Ok(primary);
+        }
+
+        let mut chain = vec![primary];
+        for fallback in resolver.fallbacks.iter() {
+            chain.push(self.build_config(fallback, &resolver.constraint)?);
+        }
+
+        Ok(Arc::new(ResolverChain::new(chain, resolver.mirror)?))
+    }
+
+    fn build_config(
+        &self,
+        config: &ResolverConfig,
+        constraint: &ResolverConstraint,
+    ) -> Result<Arc<dyn LocationResolver>> {
+        let r: Arc<dyn LocationResolver> = match config {
+            ResolverConfig::DiskResolver { root } => Arc::new(DiskResolver::new(root.clone())),
+            &ResolverConfig::GithubResolver { github_area } => {
+                Arc::new(GithubResolver::new(github_area))
+            }
+            ResolverConfig::ForgeResolver(forge) => Arc::new(ForgeResolver::new(forge)?),
+            ResolverConfig::GitResolver { git_root } => {
+                Arc::new(GitResolver::new(git_root.clone()))
+            }
+            ResolverConfig::PluginResolver { plugin, args } => {
+                Arc::new(PluginResolver::new(plugin.clone(), args.clone()))
+            }
+            ResolverConfig::LinkResolver { link_root } => {
+                Arc::new(LinkResolver::new(link_root.clone()))
+            }
+            ResolverConfig::StoreResolver { store_root } => {
+                Arc::new(StoreResolver::new(store_root.clone()))
+            }
+            ResolverConfig::VaultResolver {
+                vault_root,
+                keyfile,
+                passphrase_env,
+            } => Arc::new(VaultResolver::new(
+                vault_root.clone(),
+                keyfile.clone(),
+                passphrase_env.clone(),
+            )),
+            ResolverConfig::ArchiveResolver {
+                archive_root,
+                format,
+                extract_to,
+            } => Arc::new(ArchiveResolver::new(
+                archive_root.clone(),
+                *format,
+                extract_to.clone(),
+                constraint.clone(),
+            )),
+            ResolverConfig::S3Resolver(s3) => Arc::new(S3Resolver::new(s3, constraint.clone())?),
+            ResolverConfig::WebDavResolver(webdav) => {
+                Arc::new(WebDavResolver::new(webdav, constraint.clone())?)
+            }
+            ResolverConfig::Custom { kind, options } => {
+                let factory = self
+                    .kinds
+                    .get(kind)
+                    .ok_or_else(|| anyhow!("unknown resolver kind: {}", kind))?;
+                factory(options.clone())?
+            }
+        };
+
+        Ok(r)
+    }
+
+    pub fn build(mut self) -> Result<JohnnyDecimal> {
+        let config = self.config.take().unwrap_or_default();
+
+        if !self.skip_validation {
+            config.validate()?;
+        }
+
+        let mut resolvers = std::mem::take(&mut self.resolvers);
+        for resolver in config.resolvers.iter() {
+            let r = self.build_resolver(resolver)?;
+            resolvers.push((resolver.constraint.clone(), r));
+        }
+
+        let backend = match self.backend.take() {
+            Some(b) => b,
+            None => Box::new(FileIndexBackend::new(config.index_path.clone())),
+        };
+
+        let index = match self.index.take() {
+            Some(i) => i,
+            None => backend.load().unwrap_or_default(),
+        };
+
+        Ok(JohnnyDecimal::from_parts(config, index, backend, resolvers))
+    }
+}
@@ -1,50 +1,219 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 
-use crate::config::ResolverConfig;
-use crate::resolver::{DiskResolver, GithubResolver};
-use crate::{Config, Index, Item, Location, LocationResolver, ResolverConstraint, ID};
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+use crate::resolver::{copy_into, holds, scratch_copy};
+use crate::usage::{self, ItemUsage, UsageLog};
+use crate::Tags;
+use crate::{
+    Checkout, Config, ContentHashes, ContentHit, ContentIndex, Duplicate, HitLocation, Index,
+    IndexBackend, Item, ItemStatus, JohnnyDecimalBuilder, Location, LocationResolver, Query,
+    ResolverConstraint, SearchHit, ID,
+};
+
+/// Progress of a category migration, kept beside the index so it can be resumed.
+#[derive(Debug, Deserialize, Serialize)]
+struct MigrationJournal {
+    category: usize,
+    to_resolver: String,
+    migrated: Vec<usize>,
+}
+
+fn hash_content(root: &Path, path: &Path, hashes: &mut ContentHashes) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    let relative = path
+        .strip_prefix(root)?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            hash_content(root, &entry?.path(), hashes)?;
+        }
+        return Ok(());
+    }
+
+    let mut hasher = Sha256::new();
+    if metadata.file_type().is_symlink() {
+        // Links are compared by target, what they point to isn't part of the item.
+        hasher.update(fs::read_link(path)?.to_string_lossy().as_bytes());
+    } else {
+        io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    }
+    hashes.insert(relative, format!("{:x}", hasher.finalize()));
+    Ok(())
+}
+
+/// Hashes the content of a file or directory, used to verify copies.
+fn content_hashes(path: &Path) -> Result<ContentHashes> {
+    let mut hashes = ContentHashes::new();
+    hash_content(path, path, &mut hashes)?;
+    Ok(hashes)
+}
+
+/// Checks that the resolver hands back every file of `expected` unchanged.
+///
+/// Resolvers may keep files of their own beside the content, e.g. the manifest of a store.
+fn verify_copy(
+    resolver: &dyn LocationResolver,
+    item: &Item,
+    expected: &ContentHashes,
+    index: &Index,
+) -> Result<()> {
+    let stored = match resolver.remote_hashes(item, index)? {
+        Some(stored) => stored,
+        None => checkout_hashes(resolver, item, expected, index)?,
+    };
+
+    // A lone file is filed inside the item by resolvers that keep items as folders.
+    let lone = match (expected.get(""), stored.len()) {
+        (Some(hash), 1) if expected.len() == 1 => stored.values().next() == Some(hash),
+        _ => false,
+    };
+
+    for (file, hash) in expected.iter() {
+        if !lone && stored.get(file) != Some(hash) {
+            bail!(
+                "{} was copied incorrectly, {} differs",
+                item,
+                if file.is_empty() { "its content" } else { file }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Hashes the content the resolver hands out when the item is checked out.
+fn checkout_hashes(
+    resolver: &dyn LocationResolver,
+    item: &Item,
+    expected: &ContentHashes,
+    index: &Index,
+) -> Result<ContentHashes> {
+    let checkout = resolver
+        .checkout(item, index)?
+        .ok_or_else(|| anyhow!("{} is missing after the transfer", item))?;
+
+    let stored = match &checkout.location {
+        Location::Path(p) => content_hashes(p),
+        Location::URL(_) if expected.keys().ne([""].iter()) => Err(anyhow!(
+            "{} can't be verified through a URL, give its new resolver a local cache",
+            item
+        )),
+        Location::URL(u) => ureq::get(u)
+            .call()
+            .map_err(anyhow::Error::from)
+            .and_then(|response| {
+                let mut hasher = Sha256::new();
+                io::copy(&mut response.into_reader(), &mut hasher)?;
+                Ok(ContentHashes::from([(
+                    String::new(),
+                    format!("{:x}", hasher.finalize()),
+                )]))
+            })
+            .with_context(|| format!("can't read {} back to verify it", item)),
+    };
+    resolver.checkin(item, checkout, index)?;
+    stored
+}
+
+/// Latest modification time of a file or of anything in a directory, in seconds since the epoch.
+fn newest_mtime(path: &Path) -> Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    let mut newest = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            newest = newest.max(newest_mtime(&entry?.path())?);
+        }
+    }
+    Ok(newest)
+}
 
 pub struct JohnnyDecimal {
     config: Config,
     pub index: Box<Index>,
+    backend: Box<dyn IndexBackend>,
     resolvers: Vec<(ResolverConstraint, Arc<dyn LocationResolver>)>,
 }
 
 impl JohnnyDecimal {
     pub fn new(config: Config) -> Result<Self> {
-        let index = Box::new(Index::load(&config.index_path).unwrap_or_default());
-
-        let mut resolvers = Vec::new();
-        for resolver in config.resolvers.iter() {
-            // TODO: Detect resolver conflict
-            let r: Arc<dyn LocationResolver> = match &resolver.config {
-                ResolverConfig::DiskResolver { root } => Arc::new(DiskResolver::new(root.clone())),
-                &ResolverConfig::GithubResolver { github_area } => {
-                    Arc::new(GithubResolver::new(github_area))
-                }
-            };
-            resolvers.push((resolver.constraint.clone(), r));
-        }
+        Self::builder().config(config).build()
+    }
+
+    pub fn builder() -> JohnnyDecimalBuilder {
+        JohnnyDecimalBuilder::new()
+    }
 
-        Ok(Self {
+    pub(crate) fn from_parts(
+        config: Config,
+        index: Index,
+        backend: Box<dyn IndexBackend>,
+        resolvers: Vec<(ResolverConstraint, Arc<dyn LocationResolver>)>,
+    ) -> Self {
+        Self {
             config,
-            index,
+            index: Box::new(index),
+            backend,
             resolvers,
-        })
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Finds the position of the resolver serving the category.
+    /// Resolvers registered on the builder come first, then the most specific constraint wins.
+    fn resolver_position(&self, category: usize) -> Option<usize> {
+        let registered = self.resolvers.len() - self.config.resolvers.len();
+        let best = |positions: std::ops::Range<usize>| {
+            positions
+                .filter(|i| self.resolvers[*i].0.matches(category))
+                .max_by_key(|i| (self.resolvers[*i].0.specificity(), Reverse(*i)))
+        };
+
+        best(0..registered).or_else(|| best(registered..self.resolvers.len()))
     }
 
     fn find_resolver(&self, category: usize) -> Option<Arc<dyn LocationResolver>> {
-        self.resolvers
-            .iter()
-            .find(|(c, _r)| c.matches(category))
-            .map(|(_, r)| r)
-            .cloned()
+        self.resolver_position(category)
+            .map(|i| self.resolvers[i].1.clone())
     }
 
-    pub fn mv(&mut self, category: usize, source_path: &Path, id: Option<&ID>) -> Result<Item> {
+    /// Describes the resolver serving the category, `None` if no resolver matches it.
+    pub fn describe_resolver(&self, category: usize) -> Option<String> {
+        let position = self.resolver_position(category)?;
+
+        // Resolvers registered on the builder come before the ones from the config.
+        let registered = self.resolvers.len() - self.config.resolvers.len();
+        Some(match position.checked_sub(registered) {
+            Some(i) => format!("{}", self.config.resolvers[i]),
+            None => format!("registered resolver #{}", position + 1),
+        })
+    }
+
+    fn alloc_path(
+        &mut self,
+        category: usize,
+        source_path: &Path,
+        id: Option<&ID>,
+    ) -> Result<(Arc<dyn LocationResolver>, Item)> {
         let resolver = self
             .find_resolver(category)
             .ok_or_else(|| anyhow!("no resolver for category: {}", category))?;
@@ -64,11 +233,45 @@ impl JohnnyDecimal {
             .to_string_lossy()
             .to_string();
         let item = category.add_item(&name, id)?;
-        self.save()?;
+
+        Ok((resolver, item))
+    }
+
+    /// Takes back an item allocated in memory when its resolver failed to store it.
+    fn unalloc(&mut self, id: &ID) -> Result<()> {
+        self.index
+            .get_area_from_category_mut(id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?
+            .get_category_mut(id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?
+            .remove_item(id)
+    }
+
+    pub fn mv(&mut self, category: usize, source_path: &Path, id: Option<&ID>) -> Result<Item> {
+        let (resolver, item) = self.alloc_path(category, source_path, id)?;
 
         let src_location = Location::Path(PathBuf::from(source_path));
 
-        resolver.set(&item, src_location, &self.index)?;
+        if let Err(e) = resolver.set(&item, src_location, &self.index) {
+            self.unalloc(&item.id)?;
+            return Err(e);
+        }
+
+        self.save()?;
+
+        Ok(item)
+    }
+
+    /// Files a path as a new item without moving it.
+    pub fn link(&mut self, category: usize, source_path: &Path, id: Option<&ID>) -> Result<Item> {
+        let (resolver, item) = self.alloc_path(category, source_path, id)?;
+
+        if let Err(e) = resolver.link(&item, source_path, &self.index) {
+            self.unalloc(&item.id)?;
+            return Err(e);
+        }
+
+        self.save()?;
 
         Ok(item)
     }
@@ -89,7 +292,10 @@ impl JohnnyDecimal {
 
         let item = category.add_item(name, None)?;
 
-        resolver.set(&item, Location::URL(String::from(url)), &self.index)?;
+        if let Err(e) = resolver.set(&item, Location::URL(String::from(url)), &self.index) {
+            self.unalloc(&item.id)?;
+            return Err(e);
+        }
 
         self.save()?;
 
@@ -119,8 +325,9 @@ impl JohnnyDecimal {
             .find_resolver(id.category)
             .ok_or_else(|| anyhow!("no resolver for category: {}", id.category))?;
 
-        let src_path = src_resolver
-            .get(&item, &self.index)?
+        // Checkouts hand out the item's content even when the resolver stores it packed.
+        let checkout = src_resolver
+            .checkout(&item, &self.index)?
             .ok_or_else(|| anyhow!("source file not found"))?;
 
         let tgt_area = self
@@ -132,17 +339,53 @@ impl JohnnyDecimal {
             .get_category_mut(category)?
             .ok_or_else(|| anyhow!("missing area"))?;
 
-        let item = tgt_category.add_item(&item.name, None)?;
+        let old_item = item;
+        let item = tgt_category.add_item(&old_item.name, None)?;
 
         // Now that the index is updated we need to move the files.
         let dst_resolver = self
             .find_resolver(category)
             .ok_or_else(|| anyhow!("no resolver for category: {}", category))?;
-        dst_resolver.set(&item, src_path, &self.index)?;
+        let src_path = match &checkout.location {
+            // The checkout is handed back below, so the new resolver gets its own copy.
+            Location::Path(p) if checkout.needs_checkin => {
+                let copy = scratch_copy(p, &old_item, "relocate")?;
+                let result = dst_resolver.set(&item, Location::Path(copy.clone()), &self.index);
+                if let Some(scratch) = copy.parent() {
+                    if scratch.exists() {
+                        fs::remove_dir_all(scratch)?;
+                    }
+                }
+                result?;
+                Location::Path(copy)
+            }
+            location => {
+                dst_resolver.set(&item, location.clone(), &self.index)?;
+                location.clone()
+            }
+        };
+        src_resolver.checkin(&old_item, checkout, &self.index)?;
 
         // Now we save.
         self.save()?;
 
+        // Resolvers that copy rather than move still hold the old item, which a later
+        // item reusing its ID would inherit. Another resolver that merely refers to the
+        // old location, e.g. by linking to it, still needs it though.
+        let same_resolver = self.resolver_position(id.category) == self.resolver_position(category);
+        let referenced = !same_resolver && dst_resolver.refers_to(&item, &src_path, &self.index)?;
+        let same_id = item.id.category == old_item.id.category && item.id.id == old_item.id.id;
+        if !same_id {
+            if !referenced {
+                src_resolver.remove(&old_item, &self.index)?;
+            }
+            self.forget_use(&old_item.id)?;
+            self.edit_tags(|tags| {
+                tags.rename(&old_item.id, &item.id);
+                Ok(())
+            })?;
+        }
+
         Ok(item)
     }
 
@@ -167,6 +410,158 @@ impl JohnnyDecimal {
         }
     }
 
+    /// Finds the folder of a category, `None` if its resolver doesn't keep it on disk.
+    pub fn locate_category(&self, category: usize) -> Result<Option<PathBuf>> {
+        let resolver = self
+            .find_resolver(category)
+            .ok_or_else(|| anyhow!("no resolver for category: {}", category))?;
+
+        let exists = match self.index.get_area_from_category(category)? {
+            Some(area) => area.get_category(category)?.is_some(),
+            None => false,
+        };
+        if !exists {
+            return Ok(None);
+        }
+
+        resolver.category_path(category, &self.index)
+    }
+
+    /// Finds the folder of an area, `None` if none of its resolvers keep it on disk.
+    pub fn locate_area(&self, bounds: (usize, usize)) -> Result<Option<PathBuf>> {
+        if self.index.get_area(bounds)?.is_none() {
+            return Ok(None);
+        }
+
+        // Areas don't have resolvers of their own, any resolver serving one of their categories will do.
+        for category in bounds.0..=bounds.1 {
+            if let Some(resolver) = self.find_resolver(category) {
+                if let Some(path) = resolver.area_path(bounds, &self.index)? {
+                    return Ok(Some(path));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Prepares an item to be opened, see `checkin`.
+    pub fn checkout(&self, id: &ID) -> Result<Option<Checkout>> {
+        let resolver = self
+            .find_resolver(id.category)
+            .ok_or_else(|| anyhow!("no resolver for category: {}", id.category))?;
+
+        let area = self
+            .index
+            .get_area_from_category(id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        if let Some(item) = category.get_item(id)? {
+            resolver.checkout(&item, &self.index)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn checkin(&self, id: &ID, checkout: Checkout) -> Result<()> {
+        if !checkout.needs_checkin {
+            return Ok(());
+        }
+
+        let resolver = self
+            .find_resolver(id.category)
+            .ok_or_else(|| anyhow!("no resolver for category: {}", id.category))?;
+
+        let area = self
+            .index
+            .get_area_from_category(id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        let item = category
+            .get_item(id)?
+            .ok_or_else(|| anyhow!("missing item"))?;
+
+        resolver.checkin(&item, checkout, &self.index)
+    }
+
+    pub fn status(&self, id: &ID) -> Result<Option<ItemStatus>> {
+        let resolver = self
+            .find_resolver(id.category)
+            .ok_or_else(|| anyhow!("no resolver for category: {}", id.category))?;
+
+        let area = self
+            .index
+            .get_area_from_category(id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        if let Some(item) = category.get_item(id)? {
+            resolver.status(&item, &self.index)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Lists the items whose location is a link to a path that no longer exists.
+    ///
+    /// Only categories kept as plain files are checked, locating items elsewhere could fetch,
+    /// unpack or decrypt them.
+    pub fn dangling_links(&self) -> Result<Vec<(Item, PathBuf)>> {
+        let mut dangling = Vec::new();
+
+        for area in self.index.list_areas() {
+            for category in area.list_categories() {
+                let category_path = match self.find_resolver(category.id) {
+                    Some(resolver) => resolver.category_path(category.id, &self.index)?,
+                    None => None,
+                };
+                let category_path = match category_path {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                for item in category.list_items() {
+                    let p = category_path.join(format!("{}", item));
+                    if let Ok(target) = fs::read_link(&p) {
+                        if !p.exists() {
+                            dangling.push((item, target));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(dangling)
+    }
+
+    /// Drops unreferenced content from every resolver's store.
+    pub fn gc(&self) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        for (_, resolver) in self.resolvers.iter() {
+            removed.append(&mut resolver.gc(&self.index)?);
+        }
+        Ok(removed)
+    }
+
+    pub fn duplicates(&self) -> Result<Vec<Duplicate>> {
+        let mut duplicates = Vec::new();
+        for (_, resolver) in self.resolvers.iter() {
+            duplicates.append(&mut resolver.duplicates(&self.index)?);
+        }
+        Ok(duplicates)
+    }
+
     pub fn rm(&mut self, id: &ID) -> Result<()> {
         let opt_item = {
             let area = self
@@ -193,6 +588,12 @@ impl JohnnyDecimal {
                 .ok_or_else(|| anyhow!("no resolver for category: {}", id.category))?;
 
             resolver.remove(&item, &self.index)?;
+
+            self.forget_use(id)?;
+            self.edit_tags(|tags| {
+                tags.forget(id);
+                Ok(())
+            })?;
         }
 
         self.save()?;
@@ -200,12 +601,411 @@ impl JohnnyDecimal {
         Ok(())
     }
 
+    fn trash_path(&self) -> PathBuf {
+        self.config.index_path.with_file_name("trash")
+    }
+
+    /// Removes an item after copying it to the trash folder beside the index.
+    ///
+    /// The copy is taken as the resolver stores the item, so vault items stay encrypted.
+    /// Items only reachable through a URL are refused, the trash couldn't keep them.
+    /// Returns the folder holding the copy, `None` if the item doesn't exist.
+    pub fn trash(&mut self, id: &ID) -> Result<Option<PathBuf>> {
+        let area = self
+            .index
+            .get_area_from_category(id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        let item = match category.get_item(id)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let location = self.locate(id)?;
+        if let Some(Location::URL(_)) = location {
+            bail!(
+                "{} is only reachable through a URL and can't be kept in the trash, remove it instead",
+                item
+            );
+        }
+
+        let dir = self
+            .trash_path()
+            .join(format!("{} {}", item, usage::now()?));
+        fs::create_dir_all(&dir)?;
+
+        // Items missing from their resolver have nothing to keep.
+        if let Some(Location::Path(p)) = location {
+            if fs::symlink_metadata(&p).is_ok() {
+                copy_into(&p, &dir)?;
+            }
+        }
+
+        self.rm(id)?;
+        Ok(Some(dir))
+    }
+
+    /// Moves every item of the category to the named resolver, then updates and saves the config.
+    ///
+    /// The config is written back to the file it was loaded from, see `Config::save`.
+    /// Each item is copied, compared with the original on the new resolver and only then
+    /// removed from the old one. Items only reachable through a URL are refused.
+    /// Progress is journaled, so an interrupted migration picks up where it stopped when run again.
+    /// The client keeps its current resolver table, the new one applies once it is rebuilt.
+    pub fn migrate_category(&mut self, category: usize, to: &str) -> Result<Vec<Item>> {
+        let registered = self.resolvers.len() - self.config.resolvers.len();
+        let dst_position = self
+            .config
+            .find_resolver(to)
+            .map(|i| registered + i)
+            .ok_or_else(|| anyhow!("unknown resolver: {}", to))?;
+        let src_position = self
+            .resolver_position(category)
+            .ok_or_else(|| anyhow!("no resolver for category: {}", category))?;
+        ensure!(
+            src_position != dst_position,
+            "category {:02} is already served by {}",
+            category,
+            to
+        );
+
+        let items = self
+            .index
+            .get_area_from_category(category)?
+            .ok_or_else(|| anyhow!("missing area"))?
+            .get_category(category)?
+            .ok_or_else(|| anyhow!("missing category"))?
+            .list_items();
+
+        let journal_path = self
+            .config
+            .index_path
+            .with_file_name(format!("migrate-{:02}.json", category));
+        let mut journal = if journal_path.exists() {
+            let journal: MigrationJournal = serde_json::from_slice(&fs::read(&journal_path)?)?;
+            if journal.to_resolver != to {
+                bail!(
+                    "category {:02} is being migrated to {}, finish that migration first",
+                    category,
+                    journal.to_resolver
+                );
+            }
+            journal
+        } else {
+            MigrationJournal {
+                category,
+                to_resolver: String::from(to),
+                migrated: Vec::new(),
+            }
+        };
+
+        // Fails before anything moves if the table can't hand the category over.
+        let mut config = self.config.clone();
+        config.assign_category(category, to)?;
+        ensure!(
+            config.path.is_some(),
+            "the config wasn't loaded from a file, it can't be updated after the migration"
+        );
+
+        let src = self.resolvers[src_position].1.clone();
+        let dst = self.resolvers[dst_position].1.clone();
+
+        let mut migrated = Vec::new();
+        for item in items {
+            if journal.migrated.contains(&item.id.id) {
+                continue;
+            }
+
+            if holds(dst.as_ref(), &item, &self.index)? {
+                if !holds(src.as_ref(), &item, &self.index)? {
+                    // Interrupted after the old copy was removed.
+                    journal.migrated.push(item.id.id);
+                    fs::write(&journal_path, serde_json::to_vec(&journal)?)?;
+                    migrated.push(item);
+                    continue;
+                }
+
+                // Interrupted during the transfer, the new copy can't be trusted.
+                dst.remove(&item, &self.index)?;
+            }
+
+            // Checkouts hand out the item's content even when the resolver stores it packed.
+            let checkout = src
+                .checkout(&item, &self.index)?
+                .ok_or_else(|| anyhow!("{} is missing from its resolver", item))?;
+
+            let content = match checkout.location.clone() {
+                Location::Path(p) if fs::symlink_metadata(&p).is_ok() => p,
+                Location::Path(_) => bail!("{} is missing from its resolver", item),
+                Location::URL(_) => {
+                    // The URL only points at data the old resolver is about to remove.
+                    src.checkin(&item, checkout, &self.index)?;
+                    bail!(
+                        "{} is only reachable through a URL, give its resolver a local cache to migrate it",
+                        item
+                    );
+                }
+            };
+
+            let transfer = || -> Result<()> {
+                let expected = content_hashes(&content)?;
+                let copy = scratch_copy(&content, &item, "migrate")?;
+                let result = dst.set(&item, Location::Path(copy.clone()), &self.index);
+                if let Some(scratch) = copy.parent() {
+                    if scratch.exists() {
+                        fs::remove_dir_all(scratch)?;
+                    }
+                }
+                result?;
+
+                ensure!(
+                    holds(dst.as_ref(), &item, &self.index)?,
+                    "{} is missing after the transfer",
+                    item
+                );
+                verify_copy(dst.as_ref(), &item, &expected, &self.index)
+            };
+            let transferred = transfer();
+            src.checkin(&item, checkout, &self.index)?;
+            transferred?;
+            src.remove(&item, &self.index)?;
+
+            journal.migrated.push(item.id.id);
+            fs::write(&journal_path, serde_json::to_vec(&journal)?)?;
+            migrated.push(item);
+        }
+
+        config.save()?;
+
+        if journal_path.exists() {
+            fs::remove_file(&journal_path)?;
+        }
+
+        Ok(migrated)
+    }
+
+    fn content_index_path(&self) -> PathBuf {
+        self.config.index_path.with_file_name("content.json")
+    }
+
+    /// Brings the content index up to date with the items stored on disk and saves it.
+    pub fn update_content_index(&self) -> Result<ContentIndex> {
+        let path = self.content_index_path();
+        let mut content = if path.exists() {
+            // A corrupted content index is only a cache, rebuild it.
+            ContentIndex::load(&path).unwrap_or_default()
+        } else {
+            ContentIndex::default()
+        };
+
+        let mut items = Vec::new();
+        for area in self.index.list_areas() {
+            for category in area.list_categories() {
+                let resolver = match self.find_resolver(category.id) {
+                    Some(r) => r,
+                    None => continue,
+                };
+
+                for item in category.list_items() {
+                    if let Some(p) = resolver.local_path(&item, &self.index)? {
+                        items.push((item, p));
+                    }
+                }
+            }
+        }
+
+        content.update(&items)?;
+        content.save(&path)?;
+
+        Ok(content)
+    }
+
+    /// Searches the text of the files stored on disk, updating the content index first.
+    pub fn search_content(&self, term: &str) -> Result<Vec<ContentHit>> {
+        Ok(self.update_content_index()?.search(term))
+    }
+
+    fn usage_path(&self) -> PathBuf {
+        self.config.index_path.with_file_name("usage.json")
+    }
+
+    /// Loads the usage of the items opened or located so far.
+    pub fn usage(&self) -> Result<UsageLog> {
+        let path = self.usage_path();
+        if path.exists() {
+            UsageLog::load(&path)
+        } else {
+            Ok(UsageLog::default())
+        }
+    }
+
+    /// Drops the usage of a freed ID, which may be handed out again and shouldn't inherit it.
+    fn forget_use(&self, id: &ID) -> Result<()> {
+        if self.usage_path().exists() {
+            let mut usage = self.usage()?;
+            usage.forget(id);
+            usage.save(self.usage_path())?;
+        }
+        Ok(())
+    }
+
+    /// Records that the item was opened or located, see `recent` and `jump`.
+    pub fn record_use(&self, id: &ID) -> Result<()> {
+        let mut usage = self.usage()?;
+        usage.record(id, usage::now()?);
+        usage.save(self.usage_path())
+    }
+
+    fn tags_path(&self) -> PathBuf {
+        self.config.index_path.with_file_name("tags.json")
+    }
+
+    /// Loads the tags of every item.
+    pub fn tags(&self) -> Result<Tags> {
+        let path = self.tags_path();
+        if path.exists() {
+            Tags::load(&path)
+        } else {
+            Ok(Tags::default())
+        }
+    }
+
+    fn edit_tags<F: FnOnce(&mut Tags) -> Result<()>>(&self, edit: F) -> Result<()> {
+        let mut tags = self.tags()?;
+        edit(&mut tags)?;
+        tags.save(self.tags_path())
+    }
+
+    /// Tags the item, so `tag:` query terms find it. Returns its tags.
+    pub fn tag(&self, id: &ID, tag: &str) -> Result<Vec<String>> {
+        let item = self
+            .index
+            .get_area_from_category(id.category)?
+            .and_then(|a| a.get_category(id.category).ok().flatten())
+            .and_then(|c| c.get_item(id).ok().flatten());
+        ensure!(item.is_some(), "missing item: {}", id);
+        self.edit_tags(|tags| tags.add(id, tag))?;
+        Ok(self.tags()?.get(id))
+    }
+
+    /// Drops a tag from the item. Returns its remaining tags.
+    pub fn untag(&self, id: &ID, tag: &str) -> Result<Vec<String>> {
+        self.edit_tags(|tags| tags.remove(id, tag))?;
+        Ok(self.tags()?.get(id))
+    }
+
+    /// Lists the items used so far with their usage, most recently used first.
+    pub fn recent(&self) -> Result<Vec<(Item, ItemUsage)>> {
+        let usage = self.usage()?;
+
+        let mut recent = Vec::new();
+        for entry in usage.recent() {
+            let item = self
+                .index
+                .get_area_from_category(entry.id.category)?
+                .and_then(|a| a.get_category(entry.id.category).ok().flatten())
+                .and_then(|c| c.get_item(&entry.id).ok().flatten());
+
+            // Items removed since they were used are skipped.
+            if let Some(item) = item {
+                recent.push((item, entry.clone()));
+            }
+        }
+
+        Ok(recent)
+    }
+
+    /// Path of the item's content when it can be read in place, without fetching or unpacking it.
+    pub fn local_path(&self, id: &ID) -> Result<Option<PathBuf>> {
+        let resolver = self
+            .find_resolver(id.category)
+            .ok_or_else(|| anyhow!("no resolver for category: {}", id.category))?;
+
+        let item = self
+            .index
+            .get_area_from_category(id.category)?
+            .and_then(|a| a.get_category(id.category).ok().flatten())
+            .and_then(|c| c.get_item(id).ok().flatten());
+
+        match item {
+            Some(item) => resolver.local_path(&item, &self.index),
+            None => Ok(None),
+        }
+    }
+
+    /// When the item's content was last modified, if it can be read in place.
+    fn modified(&self, item: &Item) -> Result<Option<u64>> {
+        let resolver = match self.find_resolver(item.id.category) {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
+        // Unreadable content has no known age rather than failing the whole search.
+        Ok(resolver
+            .local_path(item, &self.index)?
+            .and_then(|p| newest_mtime(&p).ok()))
+    }
+
+    /// Searches the index, checking `tag:` terms against the tags of items and `after:` and
+    /// `before:` terms against their content.
+    ///
+    /// Items are dated from their local copy only, so items that can't be read in place never
+    /// match date terms.
+    pub fn search(&self, query: &Query) -> Result<Vec<SearchHit>> {
+        let mut hits = self.index.search(query);
+        if !query.has_dates() && !query.has_tags() {
+            return Ok(hits);
+        }
+
+        let tags = self.tags()?;
+        let mut kept = Vec::with_capacity(hits.len());
+        for hit in hits.drain(..) {
+            if let HitLocation::Item(item) = &hit.location {
+                if !query.matches_tags(&tags.get(&item.id)) {
+                    continue;
+                }
+                if query.has_dates() && !query.matches_modified(self.modified(item)?) {
+                    continue;
+                }
+            }
+            kept.push(hit);
+        }
+        Ok(kept)
+    }
+
+    /// Picks the item matching the query with the highest frecency.
+    /// Among items never used, the best match wins.
+    pub fn jump(&self, query: &Query) -> Result<Option<Item>> {
+        let usage = self.usage()?;
+        let now = usage::now()?;
+
+        let mut best: Option<(f64, Item)> = None;
+        for hit in self.search(query)? {
+            let item = match hit.location {
+                HitLocation::Item(item) => item,
+                _ => continue,
+            };
+
+            // Hits come best match first, so only a higher frecency replaces the current pick.
+            let frecency = usage.get(&item.id).map(|u| u.frecency(now)).unwrap_or(0.0);
+            if best.as_ref().map(|(f, _)| frecency > *f).unwrap_or(true) {
+                best = Some((frecency, item));
+            }
+        }
+
+        Ok(best.map(|(_, item)| item))
+    }
+
     pub fn save(&self) -> Result<()> {
-        self.index.save(&self.config.index_path)
+        self.backend.save(&self.index)
     }
 
     pub fn rebuild(&mut self) -> Result<()> {
-        self.index = Box::new(Index::default());
+        *self.index = Index::default();
 
         for (_, resolver) in self.resolvers.iter() {
             resolver.collect(&mut self.index)?;
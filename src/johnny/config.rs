@@ -1,21 +1,236 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::fs;
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, ensure, Result};
+
+use polyglot::Format;
 
 use serde::{Deserialize, Serialize};
 
+use crate::resolver::{ArchiveFormat, ForgeConfig, S3Config, WebDavConfig};
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
+#[allow(clippy::enum_variant_names)]
 pub enum ResolverConfig {
-    DiskResolver { root: PathBuf },
-    GithubResolver { github_area: usize },
+    DiskResolver {
+        root: PathBuf,
+    },
+    GithubResolver {
+        github_area: usize,
+    },
+    ForgeResolver(ForgeConfig),
+    GitResolver {
+        git_root: PathBuf,
+    },
+    PluginResolver {
+        plugin: PathBuf,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    LinkResolver {
+        link_root: PathBuf,
+    },
+    StoreResolver {
+        store_root: PathBuf,
+    },
+    VaultResolver {
+        vault_root: PathBuf,
+        #[serde(default)]
+        keyfile: Option<PathBuf>,
+        #[serde(default)]
+        passphrase_env: Option<String>,
+    },
+    ArchiveResolver {
+        archive_root: PathBuf,
+        #[serde(default)]
+        format: ArchiveFormat,
+        #[serde(default)]
+        extract_to: Option<PathBuf>,
+    },
+    S3Resolver(S3Config),
+    WebDavResolver(WebDavConfig),
+    /// A resolver kind registered through `JohnnyDecimalBuilder::resolver_kind`.
+    Custom {
+        kind: String,
+        #[serde(default)]
+        options: serde_json::Value,
+    },
 }
 
+impl Display for ResolverConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolverConfig::DiskResolver { root } => write!(f, "disk {}", root.display()),
+            ResolverConfig::GithubResolver { github_area } => {
+                write!(f, "github (area {:02})", github_area)
+            }
+            ResolverConfig::ForgeResolver(forge) => write!(
+                f,
+                "{} (area {:02})",
+                format!("{:?}", forge.forge).to_lowercase(),
+                forge.forge_area
+            ),
+            ResolverConfig::GitResolver { git_root } => write!(f, "git {}", git_root.display()),
+            ResolverConfig::PluginResolver { plugin, .. } => {
+                write!(f, "plugin {}", plugin.display())
+            }
+            ResolverConfig::LinkResolver { link_root } => write!(f, "link {}", link_root.display()),
+            ResolverConfig::StoreResolver { store_root } => {
+                write!(f, "store {}", store_root.display())
+            }
+            ResolverConfig::VaultResolver { vault_root, .. } => {
+                write!(f, "vault {}", vault_root.display())
+            }
+            ResolverConfig::ArchiveResolver { archive_root, .. } => {
+                write!(f, "archive {}", archive_root.display())
+            }
+            ResolverConfig::S3Resolver(s3) => write!(f, "s3 {}", s3.s3_bucket),
+            ResolverConfig::WebDavResolver(webdav) => write!(f, "webdav {}", webdav.webdav_url),
+            ResolverConfig::Custom { kind, .. } => write!(f, "{}", kind),
+        }
+    }
+}
+
+/// The categories a resolver is responsible for.
+///
+/// When several constraints match a category, the most specific one wins: `ID` and
+/// `Categories` first, then `Range` and `Glob`, then `Area`, then `Except` and finally `Default`.
 #[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(untagged)]
+#[serde(try_from = "ConstraintRepr", into = "ConstraintRepr")]
 pub enum ResolverConstraint {
+    /// A single category, e.g. `11`.
     ID(usize),
+
+    /// An inclusive range of categories, e.g. `[10, 19]`.
     Range((usize, usize)),
+
+    /// Every category of an area, designated by its lower bound like with `jd area new`,
+    /// e.g. `{"area": 30}` for `30-39`.
+    Area(usize),
+
+    /// A list of categories, e.g. `{"categories": [11, 12, 15]}`.
+    Categories(Vec<usize>),
+
+    /// Categories whose two digits match a pattern made of digits, `?` and `*`, e.g. `"3?"`.
+    Glob(String),
+
+    /// Every category not matched by the inner constraint, e.g. `{"except": {"area": 30}}`.
+    Except(Box<ResolverConstraint>),
+
+    /// Every category, `"default"`.
+    Default,
+}
+
+/// The config file representation of a `ResolverConstraint`.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ConstraintRepr {
+    ID(usize),
+    Range((usize, usize)),
+    Keyword(String),
+    Area { area: usize },
+    Categories { categories: Vec<usize> },
+    Except { except: Box<ResolverConstraint> },
+}
+
+impl TryFrom<ConstraintRepr> for ResolverConstraint {
+    type Error = anyhow::Error;
+
+    fn try_from(repr: ConstraintRepr) -> Result<Self> {
+        let constraint = match repr {
+            ConstraintRepr::ID(i) => {
+                ensure!(i < 100, "invalid category: {}", i);
+                ResolverConstraint::ID(i)
+            }
+            ConstraintRepr::Range((min, max)) => {
+                ensure!(min <= max && max < 100, "invalid range: {}-{}", min, max);
+                ResolverConstraint::Range((min, max))
+            }
+            ConstraintRepr::Keyword(k) if k == "default" => ResolverConstraint::Default,
+            ConstraintRepr::Keyword(pattern) => {
+                ensure!(
+                    !pattern.is_empty()
+                        && pattern
+                            .chars()
+                            .all(|c| c.is_ascii_digit() || c == '?' || c == '*'),
+                    "invalid category pattern: {:?}",
+                    pattern
+                );
+                ResolverConstraint::Glob(pattern)
+            }
+            ConstraintRepr::Area { area } => {
+                ensure!(
+                    area < 100 && area % 10 == 0,
+                    "invalid area: {}, expected the lower bound of an area, e.g. 30",
+                    area
+                );
+                ResolverConstraint::Area(area)
+            }
+            ConstraintRepr::Categories { categories } => {
+                if let Some(c) = categories.iter().find(|c| **c >= 100) {
+                    bail!("invalid category: {}", c);
+                }
+                ResolverConstraint::Categories(categories)
+            }
+            ConstraintRepr::Except { except } => ResolverConstraint::Except(except),
+        };
+        Ok(constraint)
+    }
+}
+
+impl From<ResolverConstraint> for ConstraintRepr {
+    fn from(constraint: ResolverConstraint) -> Self {
+        match constraint {
+            ResolverConstraint::ID(i) => ConstraintRepr::ID(i),
+            ResolverConstraint::Range(bounds) => ConstraintRepr::Range(bounds),
+            ResolverConstraint::Area(area) => ConstraintRepr::Area { area },
+            ResolverConstraint::Categories(categories) => ConstraintRepr::Categories { categories },
+            ResolverConstraint::Glob(pattern) => ConstraintRepr::Keyword(pattern),
+            ResolverConstraint::Except(except) => ConstraintRepr::Except { except },
+            ResolverConstraint::Default => ConstraintRepr::Keyword(String::from("default")),
+        }
+    }
+}
+
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            glob_matches(&pattern[1..], text)
+                || (!text.is_empty() && glob_matches(pattern, &text[1..]))
+        }
+        (Some(b'?'), Some(_)) => glob_matches(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p == t => glob_matches(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+/// Formats a sorted list of categories, collapsing consecutive ones into ranges.
+fn format_categories(categories: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for c in categories.iter() {
+        match ranges.last_mut() {
+            Some((_, max)) if *max + 1 == *c => *max = *c,
+            _ => ranges.push((*c, *c)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|(min, max)| {
+            if min == max {
+                format!("{:02}", min)
+            } else {
+                format!("{:02}-{:02}", min, max)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 impl ResolverConstraint {
@@ -23,14 +238,100 @@ impl ResolverConstraint {
         match &self {
             ResolverConstraint::ID(i) => id == *i,
             ResolverConstraint::Range((min, max)) => id >= *min && id <= *max,
+            ResolverConstraint::Area(area) => id / 10 == area / 10,
+            ResolverConstraint::Categories(categories) => categories.contains(&id),
+            ResolverConstraint::Glob(pattern) => {
+                glob_matches(pattern.as_bytes(), format!("{:02}", id).as_bytes())
+            }
+            ResolverConstraint::Except(except) => !except.matches(id),
+            ResolverConstraint::Default => true,
+        }
+    }
+
+    /// Ranks constraints when several of them match a category, higher wins.
+    pub(crate) fn specificity(&self) -> u8 {
+        match self {
+            ResolverConstraint::ID(_) | ResolverConstraint::Categories(_) => 4,
+            ResolverConstraint::Range(_) | ResolverConstraint::Glob(_) => 3,
+            ResolverConstraint::Area(_) => 2,
+            ResolverConstraint::Except(_) => 1,
+            ResolverConstraint::Default => 0,
+        }
+    }
+}
+
+impl Display for ResolverConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolverConstraint::ID(i) => write!(f, "{:02}", i),
+            ResolverConstraint::Range((min, max)) => write!(f, "{:02}-{:02}", min, max),
+            ResolverConstraint::Area(area) => write!(f, "area {:02}-{:02}", area, area + 9),
+            ResolverConstraint::Categories(categories) => {
+                let mut sorted = categories.clone();
+                sorted.sort_unstable();
+                write!(f, "{}", format_categories(&sorted))
+            }
+            ResolverConstraint::Glob(pattern) => write!(f, "{}", pattern),
+            ResolverConstraint::Except(except) => write!(f, "all except {}", except),
+            ResolverConstraint::Default => write!(f, "default"),
         }
     }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Resolver {
+    // Plain values come before the tables so the entry can be written back as TOML.
+    /// Name used to refer to the resolver from the command line, e.g. `jd cat migrate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Whether new items are also written to every fallback.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mirror: bool,
+
     pub constraint: ResolverConstraint,
     pub config: ResolverConfig,
+
+    /// Resolvers tried in order when `config` doesn't hold an item.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<ResolverConfig>,
+}
+
+impl Display for Resolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "[{}] ", name)?;
+        }
+        write!(f, "{}", self.config)?;
+        for fallback in self.fallbacks.iter() {
+            let sep = if self.mirror { "+" } else { "->" };
+            write!(f, " {} {}", sep, fallback)?;
+        }
+        Ok(())
+    }
+}
+
+/// Categories matched by the constraints of two resolvers, see `Config::overlaps`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Overlap {
+    /// Position of the resolver serving the categories.
+    pub served_by: usize,
+
+    /// Position of the other resolver matching them.
+    pub shadowed: usize,
+
+    pub categories: Vec<usize>,
+
+    /// Whether both constraints are as specific, so only the order of the resolvers decides.
+    pub tie: bool,
+
+    pub message: String,
+}
+
+impl Display for Overlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -39,6 +340,10 @@ pub struct Config {
 
     #[serde(default = "Vec::new")]
     pub resolvers: Vec<Resolver>,
+
+    /// File the config was loaded from, where `save` writes it back.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
 impl Default for Config {
@@ -54,16 +359,227 @@ impl Default for Config {
         Self {
             index_path,
             resolvers,
+            path: None,
         }
     }
 }
 
 impl Config {
+    /// Checks that resolver names are unique, so commands naming a resolver are unambiguous.
+    pub fn validate(&self) -> Result<()> {
+        let duplicates = self.duplicate_names();
+        if !duplicates.is_empty() {
+            bail!("invalid resolver table:\n  {}", duplicates.join("\n  "));
+        }
+        Ok(())
+    }
+
+    /// Describes the resolvers sharing a name.
+    pub fn duplicate_names(&self) -> Vec<String> {
+        let mut names = BTreeMap::new();
+        let mut duplicates = Vec::new();
+        for (i, resolver) in self.resolvers.iter().enumerate() {
+            if let Some(name) = &resolver.name {
+                if let Some(first) = names.insert(name, i) {
+                    duplicates.push(format!(
+                        "resolvers #{} and #{} are both named {}",
+                        first + 1,
+                        i + 1,
+                        name
+                    ));
+                }
+            }
+        }
+        duplicates
+    }
+
+    /// Lists the categories matched by the constraints of several resolvers, per pair of them.
+    /// These are not errors, the most specific constraint then the earliest resolver wins.
+    pub fn overlaps(&self) -> Vec<Overlap> {
+        let mut pairs: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+
+        for category in 0..100 {
+            let served_by = match self.serving(category) {
+                Some(i) => i,
+                None => continue,
+            };
+
+            for (i, resolver) in self.resolvers.iter().enumerate() {
+                if i != served_by && resolver.constraint.matches(category) {
+                    pairs.entry((served_by, i)).or_default().push(category);
+                }
+            }
+        }
+
+        pairs
+            .into_iter()
+            .map(|((served_by, shadowed), categories)| {
+                let describe = |i: usize| {
+                    format!(
+                        "#{} ({}: {})",
+                        i + 1,
+                        self.resolvers[i].constraint,
+                        self.resolvers[i]
+                    )
+                };
+
+                let tie = self.resolvers[served_by].constraint.specificity()
+                    == self.resolvers[shadowed].constraint.specificity();
+                let message = if tie {
+                    format!(
+                        "resolvers {} and {} both match {}, #{} serves them as it comes first",
+                        describe(served_by),
+                        describe(shadowed),
+                        format_categories(&categories),
+                        served_by + 1
+                    )
+                } else {
+                    format!(
+                        "resolver {} serves {} rather than the less specific {}",
+                        describe(served_by),
+                        format_categories(&categories),
+                        describe(shadowed)
+                    )
+                };
+
+                Overlap {
+                    served_by,
+                    shadowed,
+                    categories,
+                    tie,
+                    message,
+                }
+            })
+            .collect()
+    }
+
+    /// Finds the config file the same way `cfgloader` does.
+    fn find_file() -> Result<Option<(PathBuf, Format)>> {
+        let file = dirs::config_dir()
+            .ok_or_else(|| anyhow!("unknown config directory"))?
+            .join("dalloriam")
+            .join("jd")
+            .join("config");
+
+        Ok([
+            ("toml", Format::TOML),
+            ("json", Format::JSON),
+            ("yml", Format::YAML),
+        ]
+        .iter()
+        .map(|(ext, format)| (file.with_extension(ext), *format))
+        .find(|(path, _)| path.exists()))
+    }
+
     pub fn load() -> Result<Self> {
-        Ok(cfgloader::load_or_default(
-            "dalloriam/jd",
-            "config",
-            Self::default(),
-        )?)
+        let config: Self = cfgloader::load_or_default("dalloriam/jd", "config", Self::default())?;
+
+        // A missing config file was just created with the defaults.
+        let path = Self::find_file()?.map(|(path, _)| path);
+        Ok(Self { path, ..config })
+    }
+
+    /// Writes the config back to the file it was loaded from.
+    ///
+    /// Comments and formatting don't survive, so the previous file is kept with a `.bak` suffix.
+    pub fn save(&self) -> Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow!("the config wasn't loaded from a file"))?;
+
+        let format = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Format::JSON,
+            Some("yml") | Some("yaml") => Format::YAML,
+            _ => Format::TOML,
+        };
+        let data = polyglot::to_vec(self, format)?;
+
+        let mut name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("invalid config path: {:?}", path))?
+            .to_os_string();
+        if path.exists() {
+            let mut backup = name.clone();
+            backup.push(".bak");
+            fs::copy(path, path.with_file_name(backup))?;
+        }
+
+        name.push(".tmp");
+        let tmp = path.with_file_name(name);
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Finds the position of the resolver serving the category, the most specific one then the earliest.
+    pub fn serving(&self, category: usize) -> Option<usize> {
+        self.resolvers
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.constraint.matches(category))
+            .max_by_key(|(i, r)| (r.constraint.specificity(), Reverse(*i)))
+            .map(|(i, _)| i)
+    }
+
+    /// Finds the position of the resolver with the given name.
+    pub fn find_resolver(&self, name: &str) -> Option<usize> {
+        self.resolvers
+            .iter()
+            .position(|r| r.name.as_deref() == Some(name))
+    }
+
+    /// Makes the named resolver serve the category.
+    ///
+    /// The category is dropped from the explicit constraints of the other resolvers,
+    /// then added to the named resolver's constraint when it lists categories.
+    /// Other constraints are kept, so they must already give the category to the resolver.
+    pub fn assign_category(&mut self, category: usize, name: &str) -> Result<()> {
+        ensure!(category < 100, "invalid category: {}", category);
+
+        for resolver in self.resolvers.iter_mut() {
+            if resolver.name.as_deref() == Some(name) {
+                continue;
+            }
+            if let ResolverConstraint::Categories(categories) = &mut resolver.constraint {
+                categories.retain(|c| *c != category);
+            }
+        }
+        self.resolvers.retain(|r| match &r.constraint {
+            ResolverConstraint::ID(id) => *id != category || r.name.as_deref() == Some(name),
+            ResolverConstraint::Categories(categories) => !categories.is_empty(),
+            _ => true,
+        });
+
+        let position = self
+            .find_resolver(name)
+            .ok_or_else(|| anyhow!("unknown resolver: {}", name))?;
+        let target = &mut self.resolvers[position];
+
+        match &mut target.constraint {
+            ResolverConstraint::ID(id) if *id != category => {
+                let mut categories = vec![*id, category];
+                categories.sort_unstable();
+                target.constraint = ResolverConstraint::Categories(categories);
+            }
+            ResolverConstraint::Categories(categories) if !categories.contains(&category) => {
+                categories.push(category);
+                categories.sort_unstable();
+            }
+            // Adding an entry with the same config would create a second instance of the resolver.
+            // Lists already holding the category need no change either.
+            _ => {}
+        }
+
+        ensure!(
+            self.serving(category) == Some(position),
+            "resolver {} ({}) doesn't cover category {:02}, list the categories it serves in its constraint",
+            name,
+            self.resolvers[position].constraint,
+            category
+        );
+
+        self.validate()
     }
 }
@@ -0,0 +1,239 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Result;
+
+use serde::{Deserialize, Serialize};
+
+use crate::search::fold;
+use crate::Item;
+
+/// Extensions of the files indexed as plain text.
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "text", "md", "markdown", "rst", "org", "adoc", "tex", "csv", "tsv", "log", "json",
+    "toml", "yaml", "yml", "ini", "cfg", "conf", "xml", "html", "htm", "css", "rs", "py", "js",
+    "ts", "jsx", "tsx", "go", "c", "h", "cc", "cpp", "hpp", "java", "kt", "swift", "rb", "php",
+    "lua", "sh", "bash", "zsh", "fish", "sql", "r", "jl", "hs", "ml", "ex", "exs", "clj", "scala",
+];
+
+/// Files larger than this are left out of the index.
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Longest snippet returned with a hit, in characters.
+const SNIPPET_LENGTH: usize = 120;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ContentEntry {
+    item: Item,
+    modified: SystemTime,
+    text: String,
+}
+
+/// A file whose text matches a content search.
+#[derive(Clone, Debug, Serialize)]
+pub struct ContentHit {
+    pub item: Item,
+    pub path: PathBuf,
+
+    /// Line of the first match, starting at 1.
+    pub line: usize,
+    pub snippet: String,
+}
+
+/// Text extracted from the files of every item, kept up to date by modification time.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ContentIndex {
+    files: BTreeMap<PathBuf, ContentEntry>,
+
+    /// Files and folders that couldn't be read during the last update, with the reason.
+    #[serde(skip)]
+    skipped: Vec<(PathBuf, String)>,
+}
+
+fn is_indexed(path: &Path) -> bool {
+    let extension = match path.extension() {
+        Some(e) => e.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+    extension == "pdf" || TEXT_EXTENSIONS.contains(&extension.as_str())
+}
+
+/// Lists the indexed files under the item's path, skipping hidden files.
+/// Entries that can't be read are added to `skipped` instead.
+fn list_files(path: &Path, files: &mut Vec<PathBuf>, skipped: &mut Vec<(PathBuf, String)>) {
+    let metadata = match fs::metadata(path) {
+        Ok(m) => m,
+        Err(e) => return skipped.push((path.to_path_buf(), e.to_string())),
+    };
+    if !metadata.is_dir() {
+        // Sockets, pipes and devices are left out like other unindexed files.
+        if metadata.is_file() && is_indexed(path) && metadata.len() <= MAX_FILE_SIZE {
+            files.push(path.to_path_buf());
+        }
+        return;
+    }
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => return skipped.push((path.to_path_buf(), e.to_string())),
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        // Links inside items could lead anywhere, only the item itself may be one.
+        if entry.file_type().map(|t| t.is_symlink()).unwrap_or(false) {
+            continue;
+        }
+
+        list_files(&entry.path(), files, skipped);
+    }
+}
+
+fn extract_text(path: &Path) -> Result<String> {
+    let is_pdf = path
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("pdf"))
+        .unwrap_or(false);
+
+    if is_pdf {
+        // Unreadable PDFs are indexed without text so they aren't parsed again until they change.
+        // The parser panics on some malformed files, which shouldn't abort the whole update.
+        let text = std::panic::catch_unwind(|| pdf_extract::extract_text(path));
+        return Ok(text.ok().and_then(|t| t.ok()).unwrap_or_default());
+    }
+
+    Ok(String::from_utf8_lossy(&fs::read(path)?).into_owned())
+}
+
+/// Finds the term in the line ignoring case, returning the position of its first character.
+fn find_folded(line: &[char], term: &[char]) -> Option<usize> {
+    if term.is_empty() || term.len() > line.len() {
+        return None;
+    }
+    line.windows(term.len())
+        .position(|w| w.iter().zip(term).all(|(a, b)| fold(*a) == *b))
+}
+
+/// Cuts the line down to a window around the match.
+fn snippet(line: &[char], start: usize, length: usize) -> String {
+    if line.len() <= SNIPPET_LENGTH {
+        return line.iter().collect::<String>().trim().to_string();
+    }
+
+    let context = SNIPPET_LENGTH.saturating_sub(length) / 2;
+    let from = start.saturating_sub(context);
+    let to = (from + SNIPPET_LENGTH).min(line.len());
+    let from = to.saturating_sub(SNIPPET_LENGTH);
+
+    let mut snippet = line[from..to].iter().collect::<String>().trim().to_string();
+    if from > 0 {
+        snippet = format!("…{}", snippet);
+    }
+    if to < line.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+impl ContentIndex {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = fs::File::open(path.as_ref())?;
+        Ok(serde_json::from_reader(f)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        serde_json::to_writer(fs::File::create(&tmp)?, &self)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Indexes the files of the given items, only reading the ones modified since the last update.
+    /// Files that are gone or belong to items not listed are dropped, as are files that can't
+    /// be read, which are listed by `skipped` until the next update.
+    /// Returns the number of files read.
+    pub fn update(&mut self, items: &[(Item, PathBuf)]) -> Result<usize> {
+        let mut seen = BTreeSet::new();
+        let mut read = 0;
+        self.skipped.clear();
+
+        for (item, root) in items {
+            let mut files = Vec::new();
+            list_files(root, &mut files, &mut self.skipped);
+
+            for path in files {
+                let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        self.skipped.push((path, e.to_string()));
+                        continue;
+                    }
+                };
+
+                if let Some(entry) = self.files.get_mut(&path) {
+                    if entry.modified == modified {
+                        entry.item = item.clone();
+                        seen.insert(path);
+                        continue;
+                    }
+                }
+
+                let text = match extract_text(&path) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        self.skipped.push((path, e.to_string()));
+                        continue;
+                    }
+                };
+                seen.insert(path.clone());
+                self.files.insert(
+                    path,
+                    ContentEntry {
+                        item: item.clone(),
+                        modified,
+                        text,
+                    },
+                );
+                read += 1;
+            }
+        }
+
+        self.files.retain(|path, _| seen.contains(path));
+        Ok(read)
+    }
+
+    /// Files and folders left out of the last update because they couldn't be read.
+    pub fn skipped(&self) -> &[(PathBuf, String)] {
+        &self.skipped
+    }
+
+    /// Finds the files containing the term, ignoring case, with the first matching line of each.
+    pub fn search(&self, term: &str) -> Vec<ContentHit> {
+        let term = term.chars().map(fold).collect::<Vec<_>>();
+
+        let mut hits = self
+            .files
+            .iter()
+            .filter_map(|(path, entry)| {
+                entry.text.lines().enumerate().find_map(|(n, line)| {
+                    let chars = line.chars().collect::<Vec<_>>();
+                    find_folded(&chars, &term).map(|start| ContentHit {
+                        item: entry.item.clone(),
+                        path: path.clone(),
+                        line: n + 1,
+                        snippet: snippet(&chars, start, term.len()),
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        hits.sort_by_key(|h| (h.item.id.category, h.item.id.id));
+        hits
+    }
+}
@@ -9,7 +9,8 @@ use rayon::prelude::*;
 
 use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{Item, ID};
+use crate::search::{self, HitLocation, Matcher, Pattern, SearchHit};
+use crate::{Item, Query, ID};
 
 fn from_vec<'de, D>(deserializer: D) -> std::result::Result<[Option<Box<Item>>; 1000], D::Error>
 where
@@ -124,13 +125,31 @@ impl Category {
         Ok(())
     }
 
-    pub fn search(&self, query: &str) -> Vec<Item> {
-        self.items
-            .par_iter()
-            .filter_map(|f| f.as_ref())
-            .filter(|item| item.name.to_lowercase().contains(query))
-            .map(|i| *i.clone())
-            .collect()
+    pub fn search<M: Matcher>(&self, matcher: &M) -> Vec<SearchHit> {
+        let mut hits = Vec::new();
+
+        if matcher.may_match_items(self.id) {
+            hits = self
+                .items
+                .par_iter()
+                .filter_map(|f| f.as_ref())
+                .filter_map(|item| {
+                    matcher
+                        .match_item(item)
+                        .map(|m| SearchHit::new(HitLocation::Item(*item.clone()), &item.name, m))
+                })
+                .collect::<Vec<_>>();
+        }
+
+        if let Some(m) = matcher.match_category(self) {
+            hits.push(SearchHit::new(
+                HitLocation::Category(self.id),
+                &self.name,
+                m,
+            ));
+        }
+
+        hits
     }
 }
 
@@ -226,15 +245,26 @@ impl Area {
             .collect::<Vec<_>>()
     }
 
-    pub fn search(&self, query: &str) -> Vec<Item> {
-        self.categories
+    pub fn search<M: Matcher>(&self, matcher: &M) -> Vec<SearchHit> {
+        let mut hits = self
+            .categories
             .par_iter()
             .filter_map(|f| f.as_ref())
-            .map(|cat| cat.search(query))
+            .map(|cat| cat.search(matcher))
             .reduce(Vec::default, |mut a, mut b| {
                 a.append(&mut b);
                 a
-            })
+            });
+
+        if let Some(m) = matcher.match_area(self) {
+            hits.push(SearchHit::new(
+                HitLocation::Area(self.bounds),
+                &self.name,
+                m,
+            ));
+        }
+
+        hits
     }
 }
 
@@ -313,15 +343,35 @@ impl Index {
             .collect::<Vec<_>>()
     }
 
-    pub fn search(&self, query: &str) -> Vec<Item> {
-        let q = query.to_lowercase();
-        self.areas
+    /// Finds the areas, categories and items matching the query, from the best match to the worst.
+    /// Categories ruled out by the query are skipped without looking at their items.
+    pub fn search(&self, query: &Query) -> Vec<SearchHit> {
+        self.search_with(query)
+    }
+
+    /// Finds the names matching a regex, in tree order.
+    pub fn search_regex(&self, pattern: &str) -> Result<Vec<SearchHit>> {
+        Ok(self.search_with(&Pattern::regex(pattern)?))
+    }
+
+    /// Finds the names matching a glob as a whole, in tree order.
+    pub fn search_glob(&self, pattern: &str) -> Result<Vec<SearchHit>> {
+        Ok(self.search_with(&Pattern::glob(pattern)?))
+    }
+
+    /// Finds the names accepted by the matcher, from the best match to the worst.
+    pub fn search_with<M: Matcher>(&self, matcher: &M) -> Vec<SearchHit> {
+        let mut hits = self
+            .areas
             .par_iter()
             .filter_map(|f| f.as_ref())
-            .map(|area| area.search(&q))
+            .map(|area| area.search(matcher))
             .reduce(Vec::default, |mut a, mut b| {
                 a.append(&mut b);
                 a
-            })
+            });
+
+        search::rank(&mut hits);
+        hits
     }
 }
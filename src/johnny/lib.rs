@@ -1,13 +1,29 @@
+mod backend;
+mod builder;
 mod client;
 mod config;
+mod content;
 mod index;
 mod item;
+mod query;
 mod resolver;
+mod search;
+mod tags;
+mod usage;
 
-use resolver::LocationResolver;
-
+pub use backend::{FileIndexBackend, IndexBackend, MemoryIndexBackend};
+pub use builder::JohnnyDecimalBuilder;
 pub use client::JohnnyDecimal;
-pub use config::{Config, ResolverConstraint};
+pub use config::{Config, Overlap, Resolver, ResolverConfig, ResolverConstraint};
+pub use content::{ContentHit, ContentIndex};
 pub use index::{Area, Category, Index};
 pub use item::{Item, ID};
-pub use resolver::Location;
+pub use query::Query;
+pub use resolver::{
+    ArchiveFormat, Checkout, ContentHashes, Duplicate, ForgeConfig, ForgeKind, ItemStatus,
+    Location, LocationResolver, MemoryResolver, ResolverChain, S3Config, WebDavConfig,
+    PLUGIN_PROTOCOL_VERSIONS,
+};
+pub use search::{fuzzy_score, HitKind, HitLocation, Matcher, NameMatch, Pattern, SearchHit};
+pub use tags::Tags;
+pub use usage::{ItemUsage, UsageLog};
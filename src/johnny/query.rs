@@ -0,0 +1,412 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+use crate::search::{fuzzy_score, Matcher, NameMatch};
+use crate::tags;
+use crate::{Area, Category, Item, ID};
+
+/// Fields accepted by `field:value` terms.
+const FIELDS: &str = "area:, cat:, id:, name:, tag:, after: or before:";
+
+#[derive(Clone, Debug)]
+enum Filter {
+    /// Categories of the area starting at this bound.
+    Area(usize),
+
+    /// An inclusive range of categories.
+    Categories(usize, usize),
+
+    ID(ID),
+
+    /// Case-insensitive substring of the name, stored lowercase.
+    Name(String),
+
+    /// Fuzzy match against the name, ranks the hits.
+    Fuzzy(String),
+
+    /// Items tagged with the value, stored lowercase.
+    Tag(String),
+
+    /// Items modified on or after the day, in seconds since the epoch.
+    After(u64),
+
+    /// Items modified before the day, in seconds since the epoch.
+    Before(u64),
+}
+
+#[derive(Clone, Debug)]
+struct Clause {
+    filter: Filter,
+    negated: bool,
+}
+
+/// A search query, e.g. `tag:tax area:10-19 name:~invoice -draft`.
+///
+/// Terms are separated by whitespace and must all match:
+/// - `area:10-19` (or `area:10`) keeps an area and what it holds.
+/// - `cat:11` or `cat:11-13` keeps one or more categories and their items.
+/// - `id:11.001` keeps a single item.
+/// - `name:invoice` keeps names containing the value, `name:~invce` matches it fuzzily.
+/// - `tag:tax` keeps items tagged `tax`, see `JohnnyDecimal::search`. Only items match it.
+/// - `after:2024-01-01` and `before:2024-01-01` keep items by the UTC day their content
+///   was last modified, see `JohnnyDecimal::search`. Only items match them.
+/// - Any other word is matched fuzzily against names.
+///
+/// A leading `-` negates a term, negated words are matched as substrings rather than fuzzily.
+/// Values can be quoted, e.g. `name:"tax forms"`.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    clauses: Vec<Clause>,
+}
+
+/// Splits a query on whitespace, keeping quoted values together.
+fn tokenize(s: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    ensure!(!quoted, "invalid query: unterminated quote");
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_category(s: &str) -> Result<usize> {
+    let category = s
+        .parse::<usize>()
+        .map_err(|_| anyhow!("expected a category like 11"))?;
+    ensure!(category < 100, "category {} is out of range", category);
+    Ok(category)
+}
+
+fn parse_area(s: &str) -> Result<usize> {
+    let (lower, upper) = match s.split_once('-') {
+        Some((lower, upper)) => (lower, Some(upper)),
+        None => (s, None),
+    };
+
+    let expected = || anyhow!("expected an area like 10-19");
+    let lower = lower.parse::<usize>().map_err(|_| expected())?;
+    ensure!(lower < 100 && lower % 10 == 0, expected());
+    if let Some(upper) = upper {
+        ensure!(upper.parse::<usize>().ok() == Some(lower + 9), expected());
+    }
+
+    Ok(lower)
+}
+
+/// Parses a `YYYY-MM-DD` day into seconds since the epoch at midnight UTC.
+fn parse_day(s: &str) -> Result<u64> {
+    let expected = || anyhow!("expected a date like 2024-01-31");
+    let parts = s
+        .split('-')
+        .map(|p| p.parse::<i64>().map_err(|_| expected()))
+        .collect::<Result<Vec<_>>>()?;
+    let (year, month, day) = match parts.as_slice() {
+        [year, month, day] => (*year, *month, *day),
+        _ => return Err(expected()),
+    };
+    ensure!(year >= 1970 && (1..=12).contains(&month), expected());
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = [
+        31,
+        if leap { 29 } else { 28 },
+        31,
+        30,
+        31,
+        30,
+        31,
+        31,
+        30,
+        31,
+        30,
+        31,
+    ];
+    ensure!(
+        day >= 1 && day <= month_days[month as usize - 1],
+        "{} has no day {}",
+        &s[..s.rfind('-').unwrap_or(0)],
+        day
+    );
+
+    // Days since the epoch, counting years from March so leap days come last.
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Ok(days as u64 * 24 * 60 * 60)
+}
+
+fn parse_filter(field: &str, value: &str) -> Result<Filter> {
+    ensure!(!value.is_empty(), "missing value after {}:", field);
+
+    Ok(match field {
+        "area" => Filter::Area(parse_area(value)?),
+        "cat" | "category" => match value.split_once('-') {
+            Some((lower, upper)) => {
+                let (lower, upper) = (parse_category(lower)?, parse_category(upper)?);
+                ensure!(lower <= upper, "category range {} is reversed", value);
+                Filter::Categories(lower, upper)
+            }
+            None => {
+                let category = parse_category(value)?;
+                Filter::Categories(category, category)
+            }
+        },
+        "id" => Filter::ID(
+            value
+                .parse::<ID>()
+                .map_err(|_| anyhow!("expected an id like 11.001"))?,
+        ),
+        "name" => match value.strip_prefix('~') {
+            Some(fuzzy) => Filter::Fuzzy(String::from(fuzzy)),
+            None => Filter::Name(value.to_lowercase()),
+        },
+        "tag" => Filter::Tag(tags::normalize(value)?),
+        "after" => Filter::After(parse_day(value)?),
+        "before" => Filter::Before(parse_day(value)?),
+        _ => bail!("unknown field {}:, expected {}", field, FIELDS),
+    })
+}
+
+fn parse_clause(token: &str) -> Result<Clause> {
+    let (negated, term) = match token.strip_prefix('-') {
+        Some(term) if !term.is_empty() => (true, term),
+        _ => (false, token),
+    };
+
+    let filter = match term.split_once(':') {
+        Some((field, value)) => parse_filter(&field.to_lowercase(), value)?,
+        None if negated => Filter::Name(term.to_lowercase()),
+        None => Filter::Fuzzy(String::from(term)),
+    };
+
+    if negated {
+        if let Filter::Fuzzy(value) = &filter {
+            bail!("fuzzy terms can't be negated, use -name:{}", value);
+        }
+    }
+
+    Ok(Clause { filter, negated })
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let clauses = tokenize(s)?
+            .iter()
+            .map(|token| {
+                parse_clause(token).map_err(|e| anyhow!("invalid query term '{}': {}", token, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { clauses })
+    }
+}
+
+/// What a query term is checked against.
+enum Scope<'a> {
+    /// An area, by its lower bound.
+    Area(usize),
+    Category(usize),
+    Item(&'a ID),
+}
+
+impl Filter {
+    /// Whether a location filter matches, name filters are checked separately.
+    fn matches_scope(&self, scope: &Scope) -> bool {
+        match (self, scope) {
+            (Filter::Area(area), Scope::Area(other)) => area == other,
+            (Filter::ID(id), Scope::Item(other)) => {
+                id.category == other.category && id.id == other.id
+            }
+            (Filter::ID(_), Scope::Category(_)) => false,
+            (filter, Scope::Category(category)) => {
+                filter.matches_category(*category).unwrap_or(false)
+            }
+            (filter, Scope::Item(id)) => filter.matches_category(id.category).unwrap_or(true),
+            _ => false,
+        }
+    }
+
+    fn matches_category(&self, category: usize) -> Option<bool> {
+        match self {
+            Filter::Area(area) => Some(category / 10 == area / 10),
+            Filter::Categories(lower, upper) => Some(category >= *lower && category <= *upper),
+            Filter::ID(id) => Some(id.category == category),
+            _ => None,
+        }
+    }
+}
+
+impl Query {
+    /// Restricts the query to the area holding the category, e.g. `10-19` for `15`.
+    pub fn within_area(mut self, category: usize) -> Self {
+        self.clauses.push(Clause {
+            filter: Filter::Area(category / 10 * 10),
+            negated: false,
+        });
+        self
+    }
+
+    /// Restricts the query to a single category.
+    pub fn within_category(mut self, category: usize) -> Self {
+        self.clauses.push(Clause {
+            filter: Filter::Categories(category, category),
+            negated: false,
+        });
+        self
+    }
+
+    /// Whether any category of the area can hold matching items.
+    pub fn matches_area(&self, bounds: (usize, usize)) -> bool {
+        (bounds.0..=bounds.1).any(|c| self.matches_category(c))
+    }
+
+    /// Whether the category can hold matching items.
+    pub fn matches_category(&self, category: usize) -> bool {
+        self.clauses.iter().all(|clause| {
+            match (clause.filter.matches_category(category), clause.negated) {
+                (Some(matches), negated) => matches != negated,
+                // Filters on items can't rule out the whole category.
+                (None, _) => true,
+            }
+        })
+    }
+
+    /// Whether the query has `after:` or `before:` terms.
+    pub fn has_dates(&self) -> bool {
+        self.clauses
+            .iter()
+            .any(|c| matches!(c.filter, Filter::After(_) | Filter::Before(_)))
+    }
+
+    /// Whether an item last modified at `modified`, in seconds since the epoch, passes the
+    /// `after:` and `before:` terms. Items of unknown age only pass negated terms.
+    pub fn matches_modified(&self, modified: Option<u64>) -> bool {
+        self.clauses.iter().all(|clause| {
+            let matches = match clause.filter {
+                Filter::After(day) => modified.map(|m| m >= day),
+                Filter::Before(day) => modified.map(|m| m < day),
+                _ => return true,
+            };
+            matches.unwrap_or(false) != clause.negated
+        })
+    }
+
+    /// Whether the query has `tag:` terms.
+    pub fn has_tags(&self) -> bool {
+        self.clauses
+            .iter()
+            .any(|c| matches!(c.filter, Filter::Tag(_)))
+    }
+
+    /// Whether an item with the given tags passes the `tag:` terms.
+    pub fn matches_tags(&self, tags: &[String]) -> bool {
+        self.clauses.iter().all(|clause| match &clause.filter {
+            Filter::Tag(tag) => tags.contains(tag) != clause.negated,
+            _ => true,
+        })
+    }
+
+    /// Scores the item against the query, `None` if it doesn't match.
+    /// Only fuzzy terms contribute to the score, dates and tags are left to `matches_modified`
+    /// and `matches_tags`.
+    pub fn score(&self, item: &Item) -> Option<i64> {
+        self.score_name(&item.name, Scope::Item(&item.id))
+    }
+
+    /// Scores the category by its name, `None` if it doesn't match.
+    /// Categories never match `id:`, `tag:`, `after:` or `before:` terms.
+    pub fn score_category(&self, category: &Category) -> Option<i64> {
+        self.score_name(&category.name, Scope::Category(category.id))
+    }
+
+    /// Scores the area by its name, `None` if it doesn't match.
+    /// Areas never match `cat:`, `id:`, `tag:`, `after:` or `before:` terms.
+    pub fn score_area(&self, area: &Area) -> Option<i64> {
+        self.score_name(&area.name, Scope::Area(area.bounds.0))
+    }
+
+    fn score_name(&self, name: &str, scope: Scope) -> Option<i64> {
+        let mut score = 0;
+
+        for clause in self.clauses.iter() {
+            let matches = match &clause.filter {
+                Filter::Fuzzy(term) => match fuzzy_score(term, name) {
+                    Some(s) => {
+                        score += s;
+                        true
+                    }
+                    None => false,
+                },
+                Filter::Name(term) => name.to_lowercase().contains(term.as_str()),
+                // Item dates and tags are kept outside the index, see `matches_modified` and
+                // `matches_tags`.
+                Filter::After(_) | Filter::Before(_) | Filter::Tag(_)
+                    if matches!(scope, Scope::Item(_)) =>
+                {
+                    continue
+                }
+                filter => filter.matches_scope(&scope),
+            };
+
+            if matches == clause.negated {
+                return None;
+            }
+        }
+
+        Some(score)
+    }
+}
+
+impl Matcher for Query {
+    fn match_area(&self, area: &Area) -> Option<NameMatch> {
+        self.score_area(area).map(|score| NameMatch {
+            score,
+            ..Default::default()
+        })
+    }
+
+    fn match_category(&self, category: &Category) -> Option<NameMatch> {
+        self.score_category(category).map(|score| NameMatch {
+            score,
+            ..Default::default()
+        })
+    }
+
+    fn match_item(&self, item: &Item) -> Option<NameMatch> {
+        self.score(item).map(|score| NameMatch {
+            score,
+            ..Default::default()
+        })
+    }
+
+    fn may_match_items(&self, category: usize) -> bool {
+        self.matches_category(category)
+    }
+}
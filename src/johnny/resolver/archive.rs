@@ -0,0 +1,488 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+use lazy_static::lazy_static;
+
+use regex::Regex;
+
+use serde::{Deserialize, Serialize};
+
+use zip::write::SimpleFileOptions;
+
+use super::{Checkout, DiskResolver, Location, LocationResolver};
+use crate::{Index, Item, ResolverConstraint, ID};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "tar.zst")]
+    TarZst,
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarZst => "tar.zst",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+}
+
+fn add_to_zip<W: Write + io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    path: &Path,
+    name: &str,
+) -> Result<()> {
+    let options = SimpleFileOptions::default();
+
+    if path.is_dir() {
+        if !name.is_empty() {
+            zip.add_directory(name, options)?;
+        }
+
+        for entry in fs::read_dir(path)?.filter_map(|f| f.ok()) {
+            let entry_name = entry.file_name().to_string_lossy().to_string();
+            let child_name = if name.is_empty() {
+                entry_name
+            } else {
+                format!("{}/{}", name, entry_name)
+            };
+            add_to_zip(zip, &entry.path(), &child_name)?;
+        }
+    } else {
+        zip.start_file(name, options)?;
+        io::copy(&mut fs::File::open(path)?, zip)?;
+    }
+
+    Ok(())
+}
+
+/// The modification time of a file, as stored in extraction stamps.
+fn mtime(path: &Path) -> Result<u128> {
+    Ok(fs::metadata(path)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)?
+        .as_nanos())
+}
+
+/// The most recent modification in a tree, deletions show up on their parent directory.
+fn newest_change(path: &Path) -> Result<SystemTime> {
+    let metadata = fs::symlink_metadata(path)?;
+    let mut newest = metadata.modified()?;
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)?.filter_map(|f| f.ok()) {
+            newest = newest.max(newest_change(&entry.path())?);
+        }
+    }
+    Ok(newest)
+}
+
+/// Stores every item as a compressed archive in a `DiskResolver`-like tree.
+pub struct ArchiveResolver {
+    layout: DiskResolver,
+    root_path: PathBuf,
+    format: ArchiveFormat,
+    extract_to: Option<PathBuf>,
+    constraint: ResolverConstraint,
+}
+
+impl ArchiveResolver {
+    pub fn new(
+        root_path: PathBuf,
+        format: ArchiveFormat,
+        extract_to: Option<PathBuf>,
+        constraint: ResolverConstraint,
+    ) -> Self {
+        Self {
+            layout: DiskResolver::new(root_path.clone()),
+            root_path,
+            format,
+            extract_to,
+            constraint,
+        }
+    }
+
+    fn archive_path(&self, item: &Item, index: &Index) -> Result<PathBuf> {
+        let category_path = self.layout.get_category_path(item.id.category, index)?;
+        Ok(category_path.join(format!("{}.{}", item, self.format.extension())))
+    }
+
+    fn pack(&self, src: &Path, dst: &Path) -> Result<()> {
+        let partial = dst.with_extension("partial");
+        let file = fs::File::create(&partial)?;
+
+        match self.format {
+            ArchiveFormat::TarZst => {
+                let encoder = zstd::Encoder::new(file, 0)?;
+                let mut tar = tar::Builder::new(encoder);
+                if src.is_dir() {
+                    tar.append_dir_all(".", src)?;
+                } else {
+                    let name = src
+                        .file_name()
+                        .ok_or_else(|| anyhow!("invalid source: {:?}", src))?;
+                    tar.append_path_with_name(src, name)?;
+                }
+                tar.into_inner()?.finish()?;
+            }
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipWriter::new(file);
+                if src.is_dir() {
+                    add_to_zip(&mut zip, src, "")?;
+                } else {
+                    let name = src
+                        .file_name()
+                        .ok_or_else(|| anyhow!("invalid source: {:?}", src))?
+                        .to_string_lossy()
+                        .to_string();
+                    add_to_zip(&mut zip, src, &name)?;
+                }
+                zip.finish()?;
+            }
+        }
+
+        fs::rename(partial, dst)?;
+        Ok(())
+    }
+
+    fn unpack(&self, src: &Path, dst: &Path) -> Result<()> {
+        fs::create_dir_all(dst)?;
+        match self.format {
+            ArchiveFormat::TarZst => {
+                let decoder = zstd::Decoder::new(fs::File::open(src)?)?;
+                tar::Archive::new(decoder).unpack(dst)?;
+            }
+            ArchiveFormat::Zip => {
+                zip::ZipArchive::new(fs::File::open(src)?)?.extract(dst)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn extracted_path(&self, item: &Item) -> Option<PathBuf> {
+        self.extract_to
+            .as_ref()
+            .map(|cache| cache.join(format!("{}", item)))
+    }
+
+    /// Folder unpacked for a checkout when archives aren't kept extracted.
+    fn scratch_path(&self, item: &Item) -> PathBuf {
+        std::env::temp_dir().join(format!("jd-unpack-{}-{}", item.id, std::process::id()))
+    }
+
+    /// Records the modification time of the archive an extracted copy comes from.
+    fn stamp_path(&self, item: &Item) -> Option<PathBuf> {
+        self.extract_to
+            .as_ref()
+            .map(|cache| cache.join(format!(".{}.stamp", item)))
+    }
+
+    /// The archive time the extracted copy was made from, and whether it was edited since.
+    fn stamp(&self, item: &Item) -> Result<Option<(u128, bool)>> {
+        let (extracted, stamp) = match (self.extracted_path(item), self.stamp_path(item)) {
+            (Some(e), Some(s)) if e.exists() && s.exists() => (e, s),
+            _ => return Ok(None),
+        };
+
+        let archived = fs::read_to_string(&stamp)?.trim().parse::<u128>()?;
+        let edited = newest_change(&extracted)? > fs::metadata(&stamp)?.modified()?;
+        Ok(Some((archived, edited)))
+    }
+
+    /// Unpacks the archive to the cache unless the copy there is up to date.
+    fn extract(&self, item: &Item, archive: &Path, extracted: &Path) -> Result<()> {
+        let archived = mtime(archive)?;
+        match self.stamp(item)? {
+            Some((stamped, _)) if stamped == archived => return Ok(()),
+            Some((_, true)) => bail!(
+                "both {:?} and its extracted copy {:?} changed",
+                archive,
+                extracted
+            ),
+            _ => {}
+        }
+
+        if extracted.exists() {
+            fs::remove_dir_all(extracted)?;
+        }
+        self.unpack(archive, extracted)?;
+        self.write_stamp(item, archived)
+    }
+
+    fn write_stamp(&self, item: &Item, archived: u128) -> Result<()> {
+        if let Some(stamp) = self.stamp_path(item) {
+            fs::write(stamp, archived.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Packs the edits made to the extracted copy back into the archive.
+    fn repack(&self, item: &Item, index: &Index) -> Result<()> {
+        let extracted = match self.extracted_path(item) {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+
+        let archive = self.archive_path(item, index)?;
+        match self.stamp(item)? {
+            Some((stamped, true)) if archive.exists() => {
+                ensure!(
+                    stamped == mtime(&archive)?,
+                    "both {:?} and its extracted copy {:?} changed",
+                    archive,
+                    extracted
+                );
+                self.pack(&extracted, &archive)?;
+                self.write_stamp(item, mtime(&archive)?)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn drop_extracted(&self, item: &Item) -> Result<()> {
+        if let Some(extracted) = self.extracted_path(item) {
+            if extracted.exists() {
+                fs::remove_dir_all(extracted)?;
+            }
+        }
+
+        if let Some(stamp) = self.stamp_path(item) {
+            if stamp.exists() {
+                fs::remove_file(stamp)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_archives(&self, path: &Path, index: &mut Index) -> Result<()> {
+        lazy_static! {
+            static ref AREA_RE: Regex = Regex::new(r"^(\d\d)-(\d\d) (.*)$").unwrap();
+            static ref CATEGORY_RE: Regex = Regex::new(r"^(\d\d) (.*)$").unwrap();
+        }
+
+        for area_entry in fs::read_dir(path)?.filter_map(|f| f.ok()) {
+            let area_name = area_entry.file_name().to_string_lossy().to_string();
+            let cap = match AREA_RE.captures(&area_name) {
+                Some(c) if area_entry.path().is_dir() => c,
+                _ => continue,
+            };
+
+            let bounds = (cap[1].parse::<usize>()?, cap[2].parse::<usize>()?);
+            ensure!(
+                bounds.0 + 9 == bounds.1,
+                "invalid directory bounds: {}-{}",
+                bounds.0,
+                bounds.1
+            );
+
+            for category_entry in fs::read_dir(area_entry.path())?.filter_map(|f| f.ok()) {
+                let category_name = category_entry.file_name().to_string_lossy().to_string();
+                let cat_cap = match CATEGORY_RE.captures(&category_name) {
+                    Some(c) if category_entry.path().is_dir() => c,
+                    _ => continue,
+                };
+
+                let category_id = cat_cap[1].parse::<usize>()?;
+                if !self.constraint.matches(category_id) {
+                    continue;
+                }
+
+                if index.get_area(bounds)?.is_none() {
+                    index.create_area(bounds, &cap[3])?;
+                }
+
+                let area = index
+                    .get_area_mut(bounds)?
+                    .ok_or_else(|| anyhow!("missing area"))?;
+
+                if area.get_category(category_id)?.is_none() {
+                    area.create_category(category_id, String::from(&cat_cap[2]))?;
+                }
+
+                let category = area
+                    .get_category_mut(category_id)?
+                    .ok_or_else(|| anyhow!("missing category"))?;
+
+                for item in self.list_archives(&category_entry.path())? {
+                    category.import_item(item)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn list_archives(&self, path: &Path) -> Result<Vec<Item>> {
+        let item_re = Regex::new(&format!(
+            r"^(\d\d)\.(\d\d\d) (.*)\.{}$",
+            regex::escape(self.format.extension())
+        ))?;
+
+        let mut items = Vec::new();
+        for entry in fs::read_dir(path)?.filter_map(|f| f.ok()) {
+            let fname_str = entry.file_name().to_string_lossy().to_string();
+            if let Some(cap) = item_re.captures(&fname_str) {
+                items.push(Item {
+                    id: ID {
+                        category: cap[1].parse::<usize>()?,
+                        id: cap[2].parse::<usize>()?,
+                    },
+                    name: String::from(&cap[3]),
+                });
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+impl LocationResolver for ArchiveResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        let archive = self.archive_path(item, index)?;
+        if !archive.exists() {
+            return Ok(None);
+        }
+
+        match self.extracted_path(item) {
+            Some(extracted) => {
+                self.extract(item, &archive, &extracted)?;
+                Ok(Some(Location::Path(extracted)))
+            }
+            None => Ok(Some(Location::Path(archive))),
+        }
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        self.collect_archives(&self.root_path, index)
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let dst = self.archive_path(item, index)?;
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match src_location {
+            Location::Path(p) => {
+                let is_archive = p
+                    .file_name()
+                    .map(|n| {
+                        n.to_string_lossy()
+                            .ends_with(&format!(".{}", self.format.extension()))
+                    })
+                    .unwrap_or(false);
+
+                if p.is_file() && is_archive {
+                    // Already archived, moving it is enough.
+                    if fs::rename(&p, &dst).is_err() {
+                        fs::copy(&p, &dst)?;
+                        fs::remove_file(&p)?;
+                    }
+                } else {
+                    self.pack(&p, &dst)?;
+                    if p.is_dir() {
+                        fs::remove_dir_all(&p)?;
+                    } else {
+                        fs::remove_file(&p)?;
+                    }
+                }
+            }
+            Location::URL(u) => {
+                bail!("cannot archive a URL: {}", u);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        let archive = self.archive_path(id, index)?;
+        if archive.exists() {
+            fs::remove_file(archive)?;
+        }
+
+        self.drop_extracted(id)
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        self.layout.rename_category(category, new_name, index)
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        ensure!(old_item.id.category == new_item.id.category);
+        let old_path = self.archive_path(old_item, index)?;
+        ensure!(old_path.exists(), "archive doesn't exist: {:?}", old_path);
+
+        // The extracted copy is keyed by name, keep its edits before dropping it.
+        self.repack(old_item, index)?;
+        fs::rename(old_path, self.archive_path(new_item, index)?)?;
+
+        self.drop_extracted(old_item)
+    }
+
+    /// Hands out the archive's content: the extracted copy when `extract_to` is set,
+    /// otherwise a scratch copy unpacked for the checkout and packed back on checkin.
+    fn checkout(&self, item: &Item, index: &Index) -> Result<Option<Checkout>> {
+        if self.extract_to.is_some() {
+            // Edits left from a previous checkout that was never checked in.
+            self.repack(item, index)?;
+
+            return Ok(self.get(item, index)?.map(|location| Checkout {
+                location,
+                needs_checkin: true,
+            }));
+        }
+
+        let archive = self.archive_path(item, index)?;
+        if !archive.exists() {
+            return Ok(None);
+        }
+
+        let scratch = self.scratch_path(item);
+        if scratch.exists() {
+            fs::remove_dir_all(&scratch)?;
+        }
+        let unpacked = scratch.join(format!("{}", item));
+        self.unpack(&archive, &unpacked)?;
+        // Written last, so edits are whatever changed after it.
+        fs::write(scratch.join(".stamp"), "")?;
+
+        Ok(Some(Checkout {
+            location: Location::Path(unpacked),
+            needs_checkin: true,
+        }))
+    }
+
+    fn checkin(&self, item: &Item, checkout: Checkout, index: &Index) -> Result<()> {
+        if !checkout.needs_checkin {
+            return Ok(());
+        }
+        if self.extract_to.is_some() {
+            return self.repack(item, index);
+        }
+
+        let scratch = self.scratch_path(item);
+        let unpacked = match checkout.location {
+            Location::Path(p) if p.starts_with(&scratch) => p,
+            _ => return Ok(()),
+        };
+
+        let stamp = scratch.join(".stamp");
+        if unpacked.exists() && stamp.exists() {
+            // Untouched checkouts leave the archive as it was.
+            if newest_change(&unpacked)? > fs::metadata(&stamp)?.modified()? {
+                self.pack(&unpacked, &self.archive_path(item, index)?)?;
+            }
+        }
+
+        fs::remove_dir_all(&scratch)?;
+        Ok(())
+    }
+}
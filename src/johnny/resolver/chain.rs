@@ -0,0 +1,305 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, ensure, Result};
+
+use super::{
+    holds, scratch_copy, Checkout, ContentHashes, Duplicate, ItemStatus, Location, LocationResolver,
+};
+use crate::{Index, Item};
+
+fn holds_any(resolver: &dyn LocationResolver, items: &[Item], index: &Index) -> Result<bool> {
+    for item in items {
+        if holds(resolver, item, index)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Copies the areas and categories of the index, without their items.
+fn skeleton(index: &Index) -> Result<Index> {
+    let mut skeleton = Index::default();
+    for area in index.list_areas() {
+        let new_area = skeleton.create_area_mut(area.bounds, &area.name)?;
+        for category in area.list_categories() {
+            new_area.create_category(category.id, category.name.clone())?;
+        }
+    }
+    Ok(skeleton)
+}
+
+/// Adds the areas, categories and items of `other` missing from `index`.
+fn merge_missing(index: &mut Index, other: &Index) -> Result<()> {
+    for other_area in other.list_areas() {
+        if index.get_area(other_area.bounds)?.is_none() {
+            index.create_area(other_area.bounds, &other_area.name)?;
+        }
+
+        let area = index
+            .get_area_mut(other_area.bounds)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        for other_category in other_area.list_categories() {
+            if area.get_category(other_category.id)?.is_none() {
+                area.create_category(other_category.id, other_category.name.clone())?;
+            }
+
+            let category = area
+                .get_category_mut(other_category.id)?
+                .ok_or_else(|| anyhow!("missing category"))?;
+
+            for item in other_category.list_items() {
+                if category.get_item(&item.id)?.is_none() {
+                    category.import_item(item)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// An ordered list of resolvers backing the same categories.
+///
+/// Reads go to the first resolver holding the item, writes go to the primary resolver only,
+/// or to every resolver when mirroring.
+pub struct ResolverChain {
+    resolvers: Vec<Arc<dyn LocationResolver>>,
+    mirror: bool,
+}
+
+impl ResolverChain {
+    /// Creates a chain whose first resolver is the primary one.
+    pub fn new(resolvers: Vec<Arc<dyn LocationResolver>>, mirror: bool) -> Result<Self> {
+        ensure!(!resolvers.is_empty(), "a resolver chain can't be empty");
+        Ok(Self { resolvers, mirror })
+    }
+
+    /// Hands the mirror its own copy of the content at `src`.
+    fn set_copy(
+        &self,
+        mirror: &dyn LocationResolver,
+        item: &Item,
+        src: &Path,
+        index: &Index,
+    ) -> Result<()> {
+        let copy = scratch_copy(src, item, "mirror")?;
+        let result = mirror.set(item, Location::Path(copy.clone()), index);
+        if let Some(scratch) = copy.parent() {
+            if scratch.exists() {
+                fs::remove_dir_all(scratch)?;
+            }
+        }
+        result
+    }
+
+    fn primary(&self) -> &dyn LocationResolver {
+        self.resolvers[0].as_ref()
+    }
+
+    /// The resolvers written to on top of the primary one.
+    fn mirrors(&self) -> &[Arc<dyn LocationResolver>] {
+        if self.mirror {
+            &self.resolvers[1..]
+        } else {
+            &[]
+        }
+    }
+
+    /// The first resolver holding the item, if any.
+    fn holder(&self, item: &Item, index: &Index) -> Result<Option<&dyn LocationResolver>> {
+        for resolver in self.resolvers.iter() {
+            if holds(resolver.as_ref(), item, index)? {
+                return Ok(Some(resolver.as_ref()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The resolvers holding at least one item of the category.
+    fn category_holders(
+        &self,
+        category: usize,
+        index: &Index,
+    ) -> Result<Vec<&dyn LocationResolver>> {
+        let items = index
+            .get_area_from_category(category)?
+            .and_then(|a| a.get_category(category).ok().flatten())
+            .map(|c| c.list_items())
+            .unwrap_or_default();
+
+        let mut holders = Vec::new();
+        for (i, resolver) in self.resolvers.iter().enumerate() {
+            if i == 0 || self.mirror || holds_any(resolver.as_ref(), &items, index)? {
+                holders.push(resolver.as_ref());
+            }
+        }
+
+        Ok(holders)
+    }
+}
+
+impl LocationResolver for ResolverChain {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        match self.holder(item, index)? {
+            Some(resolver) => resolver.get(item, index),
+            None => self.primary().get(item, index),
+        }
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        self.primary().collect(index)?;
+
+        // Fallbacks usually hold copies of the same items, collect them separately
+        // so they only contribute what the primary resolver doesn't know about.
+        for resolver in self.resolvers[1..].iter() {
+            let mut other = skeleton(index)?;
+            resolver.collect(&mut other)?;
+            merge_missing(index, &other)?;
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let src = match &src_location {
+            Location::Path(p) if !self.mirrors().is_empty() => p.clone(),
+            // Mirrors keep copies of content, a URL has none to copy.
+            _ => return self.primary().set(item, src_location, index),
+        };
+
+        // Mirrors get their copies first, so a failure leaves the source where it was
+        // and the item can be dropped without orphaning its content.
+        let mut mirrored = Vec::new();
+        let mut result = Ok(());
+        for mirror in self.mirrors() {
+            result = self.set_copy(mirror.as_ref(), item, &src, index);
+            if result.is_err() {
+                break;
+            }
+            mirrored.push(mirror);
+        }
+
+        if result.is_ok() {
+            result = self.primary().set(item, src_location, index);
+        }
+
+        if result.is_err() {
+            for mirror in mirrored {
+                // The copies left behind if this fails are harmless, the first error matters.
+                let _ = mirror.remove(item, index);
+            }
+        }
+
+        result
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        for resolver in self.resolvers.iter() {
+            if holds(resolver.as_ref(), id, index)? {
+                resolver.remove(id, index)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        for resolver in self.category_holders(category, index)? {
+            resolver.rename_category(category, new_name, index)?;
+        }
+        Ok(())
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        for resolver in self.resolvers.iter() {
+            if holds(resolver.as_ref(), old_item, index)? {
+                resolver.rename_item(old_item, new_item, index)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn status(&self, item: &Item, index: &Index) -> Result<Option<ItemStatus>> {
+        match self.holder(item, index)? {
+            Some(resolver) => resolver.status(item, index),
+            None => Ok(None),
+        }
+    }
+
+    fn checkout(&self, item: &Item, index: &Index) -> Result<Option<Checkout>> {
+        match self.holder(item, index)? {
+            Some(resolver) => resolver.checkout(item, index),
+            None => self.primary().checkout(item, index),
+        }
+    }
+
+    fn checkin(&self, item: &Item, checkout: Checkout, index: &Index) -> Result<()> {
+        match self.holder(item, index)? {
+            Some(resolver) => resolver.checkin(item, checkout, index),
+            None => self.primary().checkin(item, checkout, index),
+        }
+    }
+
+    fn gc(&self, index: &Index) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        for resolver in self.resolvers.iter() {
+            removed.append(&mut resolver.gc(index)?);
+        }
+        Ok(removed)
+    }
+
+    fn duplicates(&self, index: &Index) -> Result<Vec<Duplicate>> {
+        let mut duplicates = Vec::new();
+        for resolver in self.resolvers.iter() {
+            duplicates.append(&mut resolver.duplicates(index)?);
+        }
+        Ok(duplicates)
+    }
+
+    fn refers_to(&self, item: &Item, location: &Location, index: &Index) -> Result<bool> {
+        for resolver in self.resolvers.iter() {
+            if resolver.refers_to(item, location, index)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn remote_hashes(&self, item: &Item, index: &Index) -> Result<Option<ContentHashes>> {
+        match self.holder(item, index)? {
+            Some(resolver) => resolver.remote_hashes(item, index),
+            None => Ok(None),
+        }
+    }
+
+    fn local_path(&self, item: &Item, index: &Index) -> Result<Option<PathBuf>> {
+        match self.holder(item, index)? {
+            Some(resolver) => resolver.local_path(item, index),
+            None => Ok(None),
+        }
+    }
+
+    fn category_path(&self, category: usize, index: &Index) -> Result<Option<PathBuf>> {
+        for resolver in self.resolvers.iter() {
+            if let Some(path) = resolver.category_path(category, index)? {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    fn area_path(&self, bounds: (usize, usize), index: &Index) -> Result<Option<PathBuf>> {
+        for resolver in self.resolvers.iter() {
+            if let Some(path) = resolver.area_path(bounds, index)? {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    fn link(&self, item: &Item, src_path: &Path, index: &Index) -> Result<()> {
+        self.primary().link(item, src_path, index)
+    }
+}
@@ -8,6 +8,7 @@ use lazy_static::lazy_static;
 
 use regex::Regex;
 
+use super::link::{is_symlink, remove_link, symlink};
 use super::{Location, LocationResolver};
 
 use crate::{
@@ -17,11 +18,23 @@ use crate::{
 
 pub struct DiskResolver {
     root_path: PathBuf,
+    item_filter: Option<fn(&Path) -> bool>,
 }
 
 impl DiskResolver {
     pub fn new(root_path: PathBuf) -> Self {
-        Self { root_path }
+        Self {
+            root_path,
+            item_filter: None,
+        }
+    }
+
+    /// Creates a resolver that only collects the item directories accepted by the filter.
+    pub(crate) fn filtered(root_path: PathBuf, item_filter: fn(&Path) -> bool) -> Self {
+        Self {
+            root_path,
+            item_filter: Some(item_filter),
+        }
     }
 
     fn collect_area(&self, path: &Path, area: &mut Area) -> Result<()> {
@@ -48,7 +61,13 @@ impl DiskResolver {
                 let id = cap.get(1).unwrap().as_str().parse::<usize>()?;
                 let name = cap.get(2).unwrap().as_str();
 
-                let category = area.create_category_mut(id, String::from(name))?;
+                // Other resolvers may already have collected this category.
+                let category = match area.get_category(id)? {
+                    Some(_) => area
+                        .get_category_mut(id)?
+                        .ok_or_else(|| anyhow!("missing category"))?,
+                    None => area.create_category_mut(id, String::from(name))?,
+                };
                 self.collect_category(&entry.path(), category)?;
             }
         }
@@ -61,15 +80,21 @@ impl DiskResolver {
             static ref ITEM_RE: Regex = Regex::new(r"(\d\d)\.(\d\d\d) (.*)").unwrap();
         }
 
-        for entry in fs::read_dir(&path)?.filter_map(|f| f.ok()) {
+        for entry in fs::read_dir(path)?.filter_map(|f| f.ok()) {
             if entry.file_name().to_string_lossy().starts_with('.') {
                 continue;
             }
 
-            if !entry.path().is_dir() {
+            if !entry.path().is_dir() && !is_symlink(&entry.path()) {
                 bail!("file in category root: {:?}", entry.path());
             }
 
+            if let Some(filter) = self.item_filter {
+                if !filter(&entry.path()) {
+                    continue;
+                }
+            }
+
             let fname_str = entry.file_name().to_string_lossy().to_string();
             if !ITEM_RE.is_match(&fname_str) {
                 bail!("invalid dir in category root: {:?}", entry.path());
@@ -91,7 +116,7 @@ impl DiskResolver {
         Ok(())
     }
 
-    fn get_category_path(&self, category: usize, index: &Index) -> Result<PathBuf> {
+    pub(crate) fn get_category_path(&self, category: usize, index: &Index) -> Result<PathBuf> {
         let area = index
             .get_area_from_category(category)?
             .ok_or_else(|| anyhow!("missing area"))?;
@@ -153,7 +178,14 @@ impl LocationResolver for DiskResolver {
                     upper_bound
                 );
 
-                let area = index.create_area_mut((lower_bound, upper_bound), name)?;
+                // Other resolvers may already have collected this area.
+                let bounds = (lower_bound, upper_bound);
+                let area = match index.get_area(bounds)? {
+                    Some(_) => index
+                        .get_area_mut(bounds)?
+                        .ok_or_else(|| anyhow!("missing area"))?,
+                    None => index.create_area_mut(bounds, name)?,
+                };
                 self.collect_area(&entry.path(), area)?;
             } else {
                 bail!("invalid directory");
@@ -164,6 +196,11 @@ impl LocationResolver for DiskResolver {
     }
 
     fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let src = match src_location {
+            Location::Path(p) => p,
+            Location::URL(u) => bail!("cannot store a URL on disk, download it first: {}", u),
+        };
+
         let category_path = self.get_category_path(item.id.category, index)?;
         if !category_path.exists() {
             fs::create_dir_all(&category_path)?;
@@ -171,17 +208,15 @@ impl LocationResolver for DiskResolver {
 
         let dst = category_path.join(PathBuf::from(format!("{}", item)));
 
-        match src_location {
-            Location::Path(p) => {
-                let options = CopyOptions {
-                    copy_inside: true,
-                    ..Default::default()
-                };
-                fs_extra::dir::move_dir(p, dst, &options)?;
-            }
-            Location::URL(_u) => {
-                unimplemented!("TODO: Download & store");
-            }
+        if is_symlink(&src) {
+            // Only move the link, never the content it points to.
+            fs::rename(src, dst)?;
+        } else {
+            let options = CopyOptions {
+                copy_inside: true,
+                ..Default::default()
+            };
+            fs_extra::dir::move_dir(src, dst, &options)?;
         }
 
         Ok(())
@@ -191,7 +226,9 @@ impl LocationResolver for DiskResolver {
         if let Some(loc) = self.get(id, index)? {
             match loc {
                 Location::Path(p) => {
-                    if p.exists() {
+                    if is_symlink(&p) {
+                        remove_link(&p)?;
+                    } else if p.exists() {
                         fs::remove_dir_all(p)?;
                     }
                 }
@@ -234,4 +271,37 @@ impl LocationResolver for DiskResolver {
             .ok_or_else(|| anyhow!("source category path doesn't exist"))?;
         self.set(new_item, old_path, index)
     }
+
+    fn local_path(&self, item: &Item, index: &Index) -> Result<Option<PathBuf>> {
+        Ok(match self.get(item, index)? {
+            Some(Location::Path(p)) if p.exists() => Some(p),
+            _ => None,
+        })
+    }
+
+    fn category_path(&self, category: usize, index: &Index) -> Result<Option<PathBuf>> {
+        let path = self.get_category_path(category, index)?;
+        Ok(if path.is_dir() { Some(path) } else { None })
+    }
+
+    fn area_path(&self, bounds: (usize, usize), index: &Index) -> Result<Option<PathBuf>> {
+        let area = index
+            .get_area(bounds)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let path = self.root_path.join(PathBuf::from(format!("{}", area)));
+        Ok(if path.is_dir() { Some(path) } else { None })
+    }
+
+    fn link(&self, item: &Item, src_path: &Path, index: &Index) -> Result<()> {
+        let category_path = self.get_category_path(item.id.category, index)?;
+        if !category_path.exists() {
+            fs::create_dir_all(&category_path)?;
+        }
+
+        let dst = category_path.join(PathBuf::from(format!("{}", item)));
+        symlink(&src_path.canonicalize()?, &dst)?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,361 @@
+use anyhow::{anyhow, bail, ensure, Result};
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{Location, LocationResolver};
+use crate::{Index, Item, ID};
+
+const PAGE_SIZE: usize = 50;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Github,
+    Gitlab,
+    #[serde(alias = "forgejo")]
+    Gitea,
+}
+
+/// The API shape of a specific forge.
+trait ForgeApi: Send + Sync {
+    fn list_repos(&self, org: &str) -> Result<Vec<String>>;
+    fn rename_repo(&self, org: &str, old_name: &str, new_name: &str) -> Result<()>;
+}
+
+struct Client {
+    agent: ureq::Agent,
+    api_url: String,
+    auth: Option<(&'static str, String)>,
+}
+
+impl Client {
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let req = self
+            .agent
+            .request(method, &format!("{}{}", self.api_url, path))
+            .set("Accept", "application/json");
+
+        match &self.auth {
+            Some((header, value)) => req.set(header, value),
+            None => req,
+        }
+    }
+
+    /// Fetches every page of a paginated listing, falling back to the user listing if
+    /// the org doesn't exist.
+    fn list_paginated<T, F>(
+        &self,
+        paths: &[String],
+        page_param: &str,
+        name: F,
+    ) -> Result<Vec<String>>
+    where
+        T: for<'de> Deserialize<'de>,
+        F: Fn(T) -> String,
+    {
+        for path in paths {
+            let mut names = Vec::new();
+            let mut page = 1;
+
+            loop {
+                let resp = self
+                    .request("GET", path)
+                    .query(page_param, &PAGE_SIZE.to_string())
+                    .query("page", &page.to_string())
+                    .call();
+
+                let entries: Vec<T> = match resp {
+                    Ok(r) => r.into_json()?,
+                    Err(ureq::Error::Status(404, _)) => break,
+                    Err(e) => return Err(e.into()),
+                };
+
+                let count = entries.len();
+                names.extend(entries.into_iter().map(&name));
+
+                if count < PAGE_SIZE {
+                    return Ok(names);
+                }
+                page += 1;
+            }
+        }
+
+        bail!("owner not found: {}", paths.join(", "))
+    }
+}
+
+#[derive(Deserialize)]
+struct NamedRepo {
+    name: String,
+}
+
+struct Github(Client);
+
+impl ForgeApi for Github {
+    fn list_repos(&self, org: &str) -> Result<Vec<String>> {
+        let org = encode(org);
+        let paths = [
+            format!("/orgs/{}/repos", org),
+            format!("/users/{}/repos", org),
+        ];
+        self.0
+            .list_paginated(&paths, "per_page", |r: NamedRepo| r.name)
+    }
+
+    fn rename_repo(&self, org: &str, old_name: &str, new_name: &str) -> Result<()> {
+        self.0
+            .request(
+                "PATCH",
+                &format!("/repos/{}/{}", encode(org), encode(old_name)),
+            )
+            .send_json(json!({ "name": new_name }))?;
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct GitlabProject {
+    path: String,
+}
+
+struct Gitlab(Client);
+
+impl ForgeApi for Gitlab {
+    fn list_repos(&self, org: &str) -> Result<Vec<String>> {
+        let org = encode(org);
+        let paths = [
+            format!("/groups/{}/projects", org),
+            format!("/users/{}/projects", org),
+        ];
+        self.0
+            .list_paginated(&paths, "per_page", |p: GitlabProject| p.path)
+    }
+
+    fn rename_repo(&self, org: &str, old_name: &str, new_name: &str) -> Result<()> {
+        let project = encode(&format!("{}/{}", org, old_name));
+        self.0
+            .request("PUT", &format!("/projects/{}", project))
+            .send_json(json!({ "name": new_name, "path": new_name }))?;
+        Ok(())
+    }
+}
+
+struct Gitea(Client);
+
+impl ForgeApi for Gitea {
+    fn list_repos(&self, org: &str) -> Result<Vec<String>> {
+        let org = encode(org);
+        let paths = [
+            format!("/orgs/{}/repos", org),
+            format!("/users/{}/repos", org),
+        ];
+        self.0
+            .list_paginated(&paths, "limit", |r: NamedRepo| r.name)
+    }
+
+    fn rename_repo(&self, org: &str, old_name: &str, new_name: &str) -> Result<()> {
+        self.0
+            .request(
+                "PATCH",
+                &format!("/repos/{}/{}", encode(org), encode(old_name)),
+            )
+            .send_json(json!({ "name": new_name }))?;
+        Ok(())
+    }
+}
+
+fn encode(s: &str) -> String {
+    utf8_percent_encode(s, NON_ALPHANUMERIC).to_string()
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ForgeConfig {
+    pub forge: ForgeKind,
+
+    /// The area whose categories map to forge organizations.
+    pub forge_area: usize,
+
+    /// The web URL of the forge, defaults to the public instance for GitHub and GitLab.
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// The API URL of the forge, derived from `base_url` when omitted.
+    #[serde(default)]
+    pub api_url: Option<String>,
+
+    /// Template used to build item URLs.
+    /// Supports `{base_url}`, `{org}`, `{name}`, `{category}` and `{id}`.
+    #[serde(default)]
+    pub url_template: Option<String>,
+
+    /// Name of the environment variable holding the API token.
+    #[serde(default)]
+    pub token_env: Option<String>,
+}
+
+struct Repo {
+    org: String,
+    name: String,
+}
+
+pub struct ForgeResolver {
+    area: usize,
+    base_url: String,
+    url_template: String,
+    api: Box<dyn ForgeApi>,
+}
+
+impl ForgeResolver {
+    pub fn new(config: &ForgeConfig) -> Result<Self> {
+        let base_url = match (&config.base_url, config.forge) {
+            (Some(u), _) => u.trim_end_matches('/').to_string(),
+            (None, ForgeKind::Github) => String::from("https://github.com"),
+            (None, ForgeKind::Gitlab) => String::from("https://gitlab.com"),
+            (None, ForgeKind::Gitea) => bail!("a base_url is required for gitea forges"),
+        };
+
+        let api_url = match (&config.api_url, config.forge) {
+            (Some(u), _) => u.trim_end_matches('/').to_string(),
+            (None, ForgeKind::Github) if config.base_url.is_none() => {
+                String::from("https://api.github.com")
+            }
+            (None, ForgeKind::Github) => format!("{}/api/v3", base_url),
+            (None, ForgeKind::Gitlab) => format!("{}/api/v4", base_url),
+            (None, ForgeKind::Gitea) => format!("{}/api/v1", base_url),
+        };
+
+        let token = config
+            .token_env
+            .as_ref()
+            .and_then(|var| std::env::var(var).ok());
+
+        let auth = token.map(|t| match config.forge {
+            ForgeKind::Github => ("Authorization", format!("Bearer {}", t)),
+            ForgeKind::Gitlab => ("PRIVATE-TOKEN", t),
+            ForgeKind::Gitea => ("Authorization", format!("token {}", t)),
+        });
+
+        let client = Client {
+            agent: ureq::AgentBuilder::new().build(),
+            api_url,
+            auth,
+        };
+
+        let api: Box<dyn ForgeApi> = match config.forge {
+            ForgeKind::Github => Box::new(Github(client)),
+            ForgeKind::Gitlab => Box::new(Gitlab(client)),
+            ForgeKind::Gitea => Box::new(Gitea(client)),
+        };
+
+        Ok(Self {
+            area: config.forge_area,
+            base_url,
+            url_template: config
+                .url_template
+                .clone()
+                .unwrap_or_else(|| String::from("{base_url}/{org}/{name}")),
+            api,
+        })
+    }
+
+    fn get_repo(&self, id: &ID, index: &Index) -> Result<Repo> {
+        ensure!(
+            id.category / 10 == self.area / 10,
+            "unhandled category: {}",
+            id.category
+        );
+        let area = index
+            .get_area_from_category(id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        let item = category
+            .get_item(id)?
+            .ok_or_else(|| anyhow!("missing item"))?;
+
+        Ok(Repo {
+            org: category.name.clone(),
+            name: item.name,
+        })
+    }
+}
+
+impl LocationResolver for ForgeResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        let repo = self.get_repo(&item.id, index)?;
+
+        let url = self
+            .url_template
+            .replace("{base_url}", &self.base_url)
+            .replace("{org}", &repo.org)
+            .replace("{name}", &repo.name)
+            .replace("{category}", &format!("{:02}", item.id.category))
+            .replace("{id}", &format!("{}", item.id));
+
+        Ok(Some(Location::URL(url)))
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        // Categories can't be discovered from the forge, so we only fill the ones already indexed.
+        let area = match index.get_area_from_category_mut(self.area)? {
+            Some(a) => a,
+            None => return Ok(()),
+        };
+
+        for category_id in area.bounds.0..=area.bounds.1 {
+            let category = match area.get_category_mut(category_id)? {
+                Some(c) => c,
+                None => continue,
+            };
+
+            let known = category
+                .list_items()
+                .into_iter()
+                .map(|i| i.name)
+                .collect::<Vec<_>>();
+
+            for repo in self.api.list_repos(&category.name)? {
+                if !known.contains(&repo) {
+                    category.add_item(&repo, None)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, _item: &Item, _src_location: Location, _index: &Index) -> Result<()> {
+        // Nothing to do here, content is on the forge.
+        Ok(())
+    }
+
+    fn remove(&self, _id: &Item, _index: &Index) -> Result<()> {
+        // We never delete repositories from the forge.
+        Ok(())
+    }
+
+    fn rename_category(&self, _category: usize, _new_name: &str, _index: &Index) -> Result<()> {
+        // This is unsupported because it would mean renaming the org.
+        bail!("unsupported operation");
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        ensure!(old_item.id.category == new_item.id.category);
+        let area = index
+            .get_area_from_category(old_item.id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(old_item.id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        self.api
+            .rename_repo(&category.name, &old_item.name, &new_item.name)
+    }
+}
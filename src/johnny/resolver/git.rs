@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+use super::{DiskResolver, ItemStatus, Location, LocationResolver};
+use crate::{Index, Item};
+
+fn is_repo(path: &Path) -> bool {
+    path.join(".git").exists()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<Option<String>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string(),
+    ))
+}
+
+/// Resolves items to local git repositories laid out like a `DiskResolver` tree.
+pub struct GitResolver {
+    disk: DiskResolver,
+}
+
+impl GitResolver {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            disk: DiskResolver::filtered(root_path, is_repo),
+        }
+    }
+
+    fn repo_path(&self, item: &Item, index: &Index) -> Result<PathBuf> {
+        match self.disk.get(item, index)? {
+            Some(Location::Path(p)) => Ok(p),
+            Some(Location::URL(u)) => bail!("incoherent location: {}", u),
+            None => Err(anyhow!("missing category directory")),
+        }
+    }
+}
+
+impl LocationResolver for GitResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        self.disk.get(item, index)
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        self.disk.collect(index)
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        match src_location {
+            Location::Path(p) => self.disk.set(item, Location::Path(p), index),
+            Location::URL(url) => {
+                let category_path = self.disk.get_category_path(item.id.category, index)?;
+                std::fs::create_dir_all(&category_path)?;
+
+                let dst = category_path.join(format!("{}", item));
+                let status = Command::new("git")
+                    .arg("clone")
+                    .arg("--quiet")
+                    // Keeps a URL starting with a dash from being read as an option.
+                    .arg("--")
+                    .arg(&url)
+                    .arg(&dst)
+                    .status()?;
+
+                ensure!(status.success(), "failed to clone {}", url);
+                Ok(())
+            }
+        }
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        self.disk.remove(id, index)
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        self.disk.rename_category(category, new_name, index)
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        self.disk.rename_item(old_item, new_item, index)
+    }
+
+    fn local_path(&self, item: &Item, index: &Index) -> Result<Option<PathBuf>> {
+        self.disk.local_path(item, index)
+    }
+
+    fn category_path(&self, category: usize, index: &Index) -> Result<Option<PathBuf>> {
+        self.disk.category_path(category, index)
+    }
+
+    fn area_path(&self, bounds: (usize, usize), index: &Index) -> Result<Option<PathBuf>> {
+        self.disk.area_path(bounds, index)
+    }
+
+    fn status(&self, item: &Item, index: &Index) -> Result<Option<ItemStatus>> {
+        let path = self.repo_path(item, index)?;
+        if !is_repo(&path) {
+            return Ok(None);
+        }
+
+        let branch = git(&path, &["symbolic-ref", "--short", "-q", "HEAD"])?;
+        let changes = git(&path, &["status", "--porcelain"])?
+            .ok_or_else(|| anyhow!("failed to get status of {:?}", path))?;
+
+        Ok(Some(ItemStatus {
+            branch,
+            dirty: !changes.is_empty(),
+        }))
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use super::{DiskResolver, Location, LocationResolver};
+use crate::{Index, Item};
+
+pub(crate) fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+pub(crate) fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+pub(crate) fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+pub(crate) fn remove_link(link: &Path) -> io::Result<()> {
+    // Directory links are directories on windows, and files everywhere else.
+    fs::remove_file(link).or_else(|_| fs::remove_dir(link))
+}
+
+/// Files items by linking them into a `DiskResolver` tree instead of moving them.
+pub struct LinkResolver {
+    disk: DiskResolver,
+}
+
+impl LinkResolver {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            disk: DiskResolver::new(root_path),
+        }
+    }
+}
+
+impl LocationResolver for LinkResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        self.disk.get(item, index)
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        self.disk.collect(index)
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        match src_location {
+            Location::Path(p) if is_symlink(&p) => {
+                // Moving an existing link keeps pointing at the same target.
+                self.disk.set(item, Location::Path(p), index)
+            }
+            Location::Path(p) => self.disk.link(item, &p, index),
+            Location::URL(u) => bail!("cannot link a URL: {}", u),
+        }
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        self.disk.remove(id, index)
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        self.disk.rename_category(category, new_name, index)
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        self.disk.rename_item(old_item, new_item, index)
+    }
+
+    fn local_path(&self, item: &Item, index: &Index) -> Result<Option<PathBuf>> {
+        self.disk.local_path(item, index)
+    }
+
+    /// Links refer to their target, compared once both sides are resolved.
+    fn refers_to(&self, item: &Item, location: &Location, index: &Index) -> Result<bool> {
+        let (link, target) = match (self.disk.get(item, index)?, location) {
+            (Some(Location::Path(link)), Location::Path(target)) if is_symlink(&link) => {
+                (link, target)
+            }
+            _ => return Ok(false),
+        };
+
+        Ok(match (link.canonicalize(), target.canonicalize()) {
+            (Ok(resolved), Ok(target)) => resolved == target,
+            // A dangling link can't refer to content that still exists.
+            _ => false,
+        })
+    }
+
+    fn category_path(&self, category: usize, index: &Index) -> Result<Option<PathBuf>> {
+        self.disk.category_path(category, index)
+    }
+
+    fn area_path(&self, bounds: (usize, usize), index: &Index) -> Result<Option<PathBuf>> {
+        self.disk.area_path(bounds, index)
+    }
+
+    fn link(&self, item: &Item, src_path: &Path, index: &Index) -> Result<()> {
+        self.disk.link(item, src_path, index)
+    }
+}
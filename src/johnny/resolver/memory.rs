@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, ensure, Result};
+
+use super::{Location, LocationResolver};
+use crate::{Index, Item};
+
+/// What is kept for an item, along with enough of the index to collect it back.
+#[derive(Clone)]
+struct Entry {
+    area: ((usize, usize), String),
+    category: String,
+    item: Item,
+    location: Location,
+}
+
+/// Keeps item locations in memory without touching the filesystem.
+#[derive(Default)]
+pub struct MemoryResolver {
+    entries: Mutex<HashMap<(usize, usize), Entry>>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entries(&self) -> Result<std::sync::MutexGuard<'_, HashMap<(usize, usize), Entry>>> {
+        self.entries
+            .lock()
+            .map_err(|_| anyhow!("memory resolver poisoned"))
+    }
+}
+
+impl LocationResolver for MemoryResolver {
+    fn get(&self, item: &Item, _index: &Index) -> Result<Option<Location>> {
+        Ok(self
+            .entries()?
+            .get(&(item.id.category, item.id.id))
+            .map(|e| e.location.clone()))
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        for entry in self.entries()?.values() {
+            let (bounds, area_name) = &entry.area;
+
+            // Other resolvers may already have collected this area or category.
+            let area = match index.get_area(*bounds)? {
+                Some(_) => index
+                    .get_area_mut(*bounds)?
+                    .ok_or_else(|| anyhow!("missing area"))?,
+                None => index.create_area_mut(*bounds, area_name)?,
+            };
+
+            let category = match area.get_category(entry.item.id.category)? {
+                Some(_) => area
+                    .get_category_mut(entry.item.id.category)?
+                    .ok_or_else(|| anyhow!("missing category"))?,
+                None => area.create_category_mut(entry.item.id.category, entry.category.clone())?,
+            };
+
+            category.import_item(entry.item.clone())?;
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let area = index
+            .get_area_from_category(item.id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(item.id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        let entry = Entry {
+            area: (area.bounds, area.name.clone()),
+            category: category.name.clone(),
+            item: item.clone(),
+            location: src_location,
+        };
+
+        self.entries()?
+            .insert((item.id.category, item.id.id), entry);
+        Ok(())
+    }
+
+    fn remove(&self, id: &Item, _index: &Index) -> Result<()> {
+        self.entries()?.remove(&(id.id.category, id.id.id));
+        Ok(())
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, _index: &Index) -> Result<()> {
+        for entry in self.entries()?.values_mut() {
+            if entry.item.id.category == category {
+                entry.category = String::from(new_name);
+            }
+        }
+        Ok(())
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, _index: &Index) -> Result<()> {
+        ensure!(old_item.id.category == new_item.id.category);
+        let mut entries = self.entries()?;
+        let mut entry = entries
+            .remove(&(old_item.id.category, old_item.id.id))
+            .ok_or_else(|| anyhow!("missing location for {}", old_item.id))?;
+        entry.item = new_item.clone();
+        entries.insert((new_item.id.category, new_item.id.id), entry);
+        Ok(())
+    }
+}
@@ -1,16 +1,31 @@
+mod archive;
+mod chain;
 mod disk;
+mod forge;
+mod git;
 mod github;
+mod link;
+mod memory;
+mod plugin;
+mod s3;
+mod store;
+mod vault;
+mod webdav;
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+
+use fs_extra::dir::CopyOptions;
 
 use serde::{Deserialize, Serialize};
 
 use crate::{Index, Item};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum Location {
     Path(PathBuf),
@@ -25,6 +40,25 @@ impl Display for Location {
     }
 }
 
+/// The working state of an item, for resolvers that track one.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ItemStatus {
+    pub branch: Option<String>,
+    pub dirty: bool,
+}
+
+/// Hashes of every file of an item by path relative to the item, a lone file has an empty path.
+pub type ContentHashes = BTreeMap<String, String>;
+
+/// A location prepared for interactive use.
+#[derive(Clone, Debug)]
+pub struct Checkout {
+    pub location: Location,
+
+    /// Whether the location must be handed back with `checkin` once the user is done.
+    pub needs_checkin: bool,
+}
+
 pub trait LocationResolver {
     fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>>;
     fn collect(&self, index: &mut Index) -> Result<()>;
@@ -32,7 +66,120 @@ pub trait LocationResolver {
     fn remove(&self, id: &Item, index: &Index) -> Result<()>;
     fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()>;
     fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()>;
+
+    fn status(&self, _item: &Item, _index: &Index) -> Result<Option<ItemStatus>> {
+        Ok(None)
+    }
+
+    /// Prepares the item to be opened.
+    fn checkout(&self, item: &Item, index: &Index) -> Result<Option<Checkout>> {
+        Ok(self.get(item, index)?.map(|location| Checkout {
+            location,
+            needs_checkin: false,
+        }))
+    }
+
+    /// Stores back an item once the user is done with its checkout.
+    fn checkin(&self, _item: &Item, _checkout: Checkout, _index: &Index) -> Result<()> {
+        Ok(())
+    }
+
+    /// Drops stored content no longer referenced by any item, returning what was removed.
+    fn gc(&self, _index: &Index) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// Lists content stored more than once across items.
+    fn duplicates(&self, _index: &Index) -> Result<Vec<Duplicate>> {
+        Ok(Vec::new())
+    }
+
+    /// Path of the item's content when it can be read in place, without fetching or unpacking it.
+    fn local_path(&self, _item: &Item, _index: &Index) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
+    /// Folder holding the items of the category, when they are kept as plain files.
+    fn category_path(&self, _category: usize, _index: &Index) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
+    /// Folder holding the categories of the area, when they are kept as plain files.
+    fn area_path(&self, _bounds: (usize, usize), _index: &Index) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+
+    /// Whether the resolver keeps the item by referring to `location` rather than holding
+    /// its own copy, in which case the content at `location` must not be removed.
+    fn refers_to(&self, item: &Item, location: &Location, index: &Index) -> Result<bool> {
+        Ok(self.get(item, index)?.as_ref() == Some(location))
+    }
+
+    /// Hashes of the item's files read straight from where the resolver keeps them, for
+    /// resolvers whose checkouts can't be read locally. `None` when the checkout is readable.
+    fn remote_hashes(&self, _item: &Item, _index: &Index) -> Result<Option<ContentHashes>> {
+        Ok(None)
+    }
+
+    /// Files the item by referencing the source path instead of moving it.
+    fn link(&self, _item: &Item, _src_path: &Path, _index: &Index) -> Result<()> {
+        bail!("linking is not supported by this resolver");
+    }
+}
+
+/// Whether the resolver actually holds the item, as opposed to only knowing where it would go.
+pub(crate) fn holds(resolver: &dyn LocationResolver, item: &Item, index: &Index) -> Result<bool> {
+    Ok(match resolver.get(item, index)? {
+        // Dangling links still count, so they can be cleaned up.
+        Some(Location::Path(p)) => fs::symlink_metadata(&p).is_ok(),
+        Some(Location::URL(_)) => true,
+        None => false,
+    })
+}
+
+/// Copies a file or directory into a directory, returning the path of the copy.
+pub(crate) fn copy_into(src: &Path, dir: &Path) -> Result<PathBuf> {
+    let name = src
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid source: {:?}", src))?;
+    let dst = dir.join(name);
+
+    if src.is_dir() {
+        let options = CopyOptions {
+            copy_inside: true,
+            ..Default::default()
+        };
+        fs_extra::dir::copy(src, &dst, &options)?;
+    } else {
+        fs::copy(src, &dst)?;
+    }
+
+    Ok(dst)
+}
+
+/// Copies a file or directory to a scratch location that can be handed to `set`.
+/// The copy lives in its own directory, which the caller removes once done.
+pub(crate) fn scratch_copy(src: &Path, item: &Item, purpose: &str) -> Result<PathBuf> {
+    let scratch =
+        std::env::temp_dir().join(format!("jd-{}-{}-{}", purpose, item.id, std::process::id()));
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch)?;
+    }
+    fs::create_dir_all(&scratch)?;
+
+    copy_into(src, &scratch)
 }
 
+pub use archive::{ArchiveFormat, ArchiveResolver};
+pub use chain::ResolverChain;
 pub use disk::DiskResolver;
+pub use forge::{ForgeConfig, ForgeKind, ForgeResolver};
+pub use git::GitResolver;
 pub use github::GithubResolver;
+pub use link::LinkResolver;
+pub use memory::MemoryResolver;
+pub use plugin::{PluginResolver, PLUGIN_PROTOCOL_VERSIONS};
+pub use s3::{S3Config, S3Resolver};
+pub use store::{Duplicate, StoreResolver};
+pub use vault::VaultResolver;
+pub use webdav::{WebDavConfig, WebDavResolver};
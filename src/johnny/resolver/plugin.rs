@@ -0,0 +1,294 @@
+//! Resolver backed by an external executable.
+//!
+//! The plugin is spawned once and speaks newline-delimited JSON over stdio. Every request is
+//! a `{"method": ..., "params": ...}` object, and every response is either `{"result": ...}`
+//! or `{"error": "message"}`.
+//!
+//! The first request is always a `handshake` carrying the protocol versions supported by jd,
+//! the plugin answers with the version it picked. The remaining methods mirror
+//! `LocationResolver`: `get`, `collect`, `set`, `remove`, `rename_category` and `rename_item`.
+//! Locations are encoded as `{"path": ...}` or `{"url": ...}`.
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{Location, LocationResolver};
+use crate::{Index, Item};
+
+/// The protocol versions supported by this client, in order of preference.
+pub const PLUGIN_PROTOCOL_VERSIONS: &[u32] = &[1];
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum WireLocation {
+    Path(PathBuf),
+    Url(String),
+}
+
+impl From<Location> for WireLocation {
+    fn from(l: Location) -> Self {
+        match l {
+            Location::Path(p) => WireLocation::Path(p),
+            Location::URL(u) => WireLocation::Url(u),
+        }
+    }
+}
+
+impl From<WireLocation> for Location {
+    fn from(l: WireLocation) -> Self {
+        match l {
+            WireLocation::Path(p) => Location::Path(p),
+            WireLocation::Url(u) => Location::URL(u),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ItemContext<'a> {
+    item: &'a Item,
+    category: String,
+    area: String,
+}
+
+#[derive(Deserialize)]
+struct WireCategory {
+    id: usize,
+    name: String,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct WireArea {
+    bounds: (usize, usize),
+    name: String,
+    #[serde(default)]
+    categories: Vec<WireCategory>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    result: Value,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl Response {
+    fn into_result(self) -> Result<Value> {
+        match self.error {
+            Some(e) => Err(anyhow!("plugin error: {}", e)),
+            None => Ok(self.result),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Handshake {
+    version: u32,
+}
+
+struct Connection {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Connection {
+    /// Sends a request and reads its response, any error here leaves the connection unusable.
+    fn call(&mut self, method: &str, params: Value) -> Result<Response> {
+        let request = json!({ "method": method, "params": params });
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| anyhow!("plugin stdin closed"))?;
+        writeln!(stdin, "{}", serde_json::to_string(&request)?)?;
+        stdin.flush()?;
+
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            bail!("plugin exited during '{}'", method);
+        }
+
+        serde_json::from_str(&line)
+            .with_context(|| format!("invalid plugin response to '{}'", method))
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Closing stdin tells the plugin to exit.
+        self.stdin.take();
+        let _ = self.child.wait();
+    }
+}
+
+pub struct PluginResolver {
+    command: PathBuf,
+    args: Vec<String>,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl PluginResolver {
+    pub fn new(command: PathBuf, args: Vec<String>) -> Self {
+        Self {
+            command,
+            args,
+            connection: Mutex::new(None),
+        }
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        let mut child = Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to spawn plugin {:?}", self.command))?;
+
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("missing stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("missing stdout"))?;
+
+        let mut conn = Connection {
+            child,
+            stdin: Some(stdin),
+            stdout: BufReader::new(stdout),
+        };
+
+        let handshake: Handshake = serde_json::from_value(
+            conn.call("handshake", json!({ "versions": PLUGIN_PROTOCOL_VERSIONS }))?
+                .into_result()?,
+        )?;
+
+        if !PLUGIN_PROTOCOL_VERSIONS.contains(&handshake.version) {
+            bail!("unsupported plugin protocol version: {}", handshake.version);
+        }
+
+        Ok(conn)
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let mut guard = self
+            .connection
+            .lock()
+            .map_err(|_| anyhow!("plugin connection poisoned"))?;
+
+        if guard.is_none() {
+            *guard = Some(self.connect()?);
+        }
+
+        let conn = guard.as_mut().unwrap(); // safe because we connect above
+        let response = match conn.call(method, params) {
+            Ok(response) => response,
+            Err(e) => {
+                // The plugin died or the stream is out of sync, the next call respawns it.
+                guard.take();
+                return Err(e);
+            }
+        };
+        Ok(serde_json::from_value(response.into_result()?)?)
+    }
+
+    fn context<'a>(&self, item: &'a Item, index: &Index) -> Result<ItemContext<'a>> {
+        let area = index
+            .get_area_from_category(item.id.category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(item.id.category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        Ok(ItemContext {
+            item,
+            category: category.name.clone(),
+            area: area.name.clone(),
+        })
+    }
+}
+
+impl LocationResolver for PluginResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        let loc: Option<WireLocation> =
+            self.call("get", serde_json::to_value(self.context(item, index)?)?)?;
+        Ok(loc.map(Location::from))
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        let areas: Vec<WireArea> = self.call("collect", json!({}))?;
+
+        for wire_area in areas {
+            if index.get_area(wire_area.bounds)?.is_none() {
+                index.create_area(wire_area.bounds, &wire_area.name)?;
+            }
+
+            let area = index
+                .get_area_mut(wire_area.bounds)?
+                .ok_or_else(|| anyhow!("missing area"))?;
+
+            for wire_category in wire_area.categories {
+                if area.get_category(wire_category.id)?.is_none() {
+                    area.create_category(wire_category.id, wire_category.name)?;
+                }
+
+                let category = area
+                    .get_category_mut(wire_category.id)?
+                    .ok_or_else(|| anyhow!("missing category"))?;
+
+                for item in wire_category.items {
+                    category.import_item(item)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let mut params = serde_json::to_value(self.context(item, index)?)?;
+        params["source"] = serde_json::to_value(WireLocation::from(src_location))?;
+        self.call::<Value>("set", params)?;
+        Ok(())
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        self.call::<Value>("remove", serde_json::to_value(self.context(id, index)?)?)?;
+        Ok(())
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        let area = index
+            .get_area_from_category(category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let old_category = area
+            .get_category(category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        self.call::<Value>(
+            "rename_category",
+            json!({
+                "category": category,
+                "area": area.name,
+                "old_name": old_category.name,
+                "new_name": new_name,
+            }),
+        )?;
+        Ok(())
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        let old = self.context(old_item, index)?;
+        let new = self.context(new_item, index)?;
+        self.call::<Value>("rename_item", json!({ "old": old, "new": new }))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,723 @@
+//! Resolver storing items in an S3-compatible bucket.
+//!
+//! Every item maps to the `<area>/<category>/<item>/` prefix of the bucket. Requests are signed
+//! with AWS Signature Version 4 and use path-style URLs, so any S3-compatible endpoint works.
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use hmac::{Hmac, Mac};
+
+use lazy_static::lazy_static;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use regex::Regex;
+
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+use super::{Location, LocationResolver};
+use crate::{Index, Item, ResolverConstraint, ID};
+
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+const DEFAULT_REGION: &str = "us-east-1";
+const DEFAULT_URL_EXPIRY: u64 = 3600;
+
+/// Characters left as-is by SigV4 URI encoding.
+const QUERY_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+const PATH_SET: &AsciiSet = &QUERY_SET.remove(b'/');
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct S3Config {
+    pub s3_bucket: String,
+
+    /// The endpoint of the object storage, defaults to AWS for the configured region.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    #[serde(default)]
+    pub region: Option<String>,
+
+    /// Key prefix under which the tree is stored.
+    #[serde(default)]
+    pub prefix: Option<String>,
+
+    /// Local folder items are synced to when opened.
+    /// Without one, `get` returns a presigned URL to the item, or to the listing of its objects
+    /// when it holds several.
+    #[serde(default)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Names of the environment variables holding the credentials.
+    /// Default to `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`.
+    #[serde(default)]
+    pub access_key_env: Option<String>,
+    #[serde(default)]
+    pub secret_key_env: Option<String>,
+
+    /// Lifetime of presigned URLs, in seconds.
+    #[serde(default)]
+    pub url_expiry: Option<u64>,
+}
+
+struct Object {
+    key: String,
+    size: u64,
+    etag: String,
+    last_modified: String,
+}
+
+impl Object {
+    /// Identifies the object's content, it changes whenever the object is written.
+    fn version(&self) -> String {
+        format!("{} {}", self.etag, self.last_modified)
+    }
+}
+
+/// Maps the relative path of every synced file in a cached item to the version it was fetched at.
+type SyncManifest = BTreeMap<String, String>;
+
+fn encode(s: &str, set: &'static AsciiSet) -> String {
+    utf8_percent_encode(s, set).to_string()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Formats a point in time as the `YYYYMMDDTHHMMSSZ` timestamp expected by SigV4.
+fn amz_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start = format!("<{}>", tag);
+    let end = format!("</{}>", tag);
+    let from = xml.find(&start)? + start.len();
+    let to = from + xml[from..].find(&end)?;
+    Some(xml_unescape(&xml[from..to]))
+}
+
+/// Turns an error response into a readable error.
+fn check(result: Result<ureq::Response, ureq::Error>) -> Result<ureq::Response> {
+    match result {
+        Ok(r) => Ok(r),
+        Err(ureq::Error::Status(code, resp)) => {
+            let body = resp.into_string().unwrap_or_default();
+            let message = xml_value(&body, "Message")
+                .or_else(|| xml_value(&body, "Code"))
+                .unwrap_or(body);
+            bail!("s3 error ({}): {}", code, message)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn list_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    for entry in fs::read_dir(dir)?.filter_map(|f| f.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else {
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Maps the key of an object below its item prefix to a path inside the item's cache folder.
+/// Empty and `.` segments are dropped, keys that would leave the folder are refused.
+fn cache_relative(key: &str) -> Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for segment in key.split('/').filter(|s| !s.is_empty() && *s != ".") {
+        let mut components = Path::new(segment).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => path.push(segment),
+            _ => bail!("refusing to sync an object outside its item: {}", key),
+        }
+    }
+    ensure!(
+        path.components().next().is_some(),
+        "refusing to sync an object without a name: {}",
+        key
+    );
+    Ok(path)
+}
+
+/// Stores every item under its own prefix in an S3-compatible bucket.
+pub struct S3Resolver {
+    agent: ureq::Agent,
+    endpoint: String,
+    host: String,
+    bucket: String,
+    region: String,
+    prefix: String,
+    cache_dir: Option<PathBuf>,
+    access_key_env: String,
+    secret_key_env: String,
+    url_expiry: u64,
+    constraint: ResolverConstraint,
+}
+
+impl S3Resolver {
+    pub fn new(config: &S3Config, constraint: ResolverConstraint) -> Result<Self> {
+        let region = config
+            .region
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_REGION));
+
+        let endpoint = match &config.endpoint {
+            Some(e) => e.trim_end_matches('/').to_string(),
+            None => format!("https://s3.{}.amazonaws.com", region),
+        };
+
+        let host = endpoint
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| anyhow!("invalid s3 endpoint: {}", endpoint))?;
+        ensure!(
+            !host.is_empty() && !host.contains('/'),
+            "invalid s3 endpoint: {}",
+            endpoint
+        );
+
+        let prefix = match config.prefix.as_deref().map(|p| p.trim_matches('/')) {
+            Some(p) if !p.is_empty() => format!("{}/", p),
+            _ => String::new(),
+        };
+
+        Ok(Self {
+            agent: ureq::AgentBuilder::new().build(),
+            host: String::from(host),
+            endpoint,
+            bucket: config.s3_bucket.clone(),
+            region,
+            prefix,
+            cache_dir: config.cache_dir.clone(),
+            access_key_env: config
+                .access_key_env
+                .clone()
+                .unwrap_or_else(|| String::from("AWS_ACCESS_KEY_ID")),
+            secret_key_env: config
+                .secret_key_env
+                .clone()
+                .unwrap_or_else(|| String::from("AWS_SECRET_ACCESS_KEY")),
+            url_expiry: config.url_expiry.unwrap_or(DEFAULT_URL_EXPIRY),
+            constraint,
+        })
+    }
+
+    fn credentials(&self) -> Result<(String, String)> {
+        let access_key = std::env::var(&self.access_key_env)
+            .with_context(|| format!("missing s3 access key in ${}", self.access_key_env))?;
+        let secret_key = std::env::var(&self.secret_key_env)
+            .with_context(|| format!("missing s3 secret key in ${}", self.secret_key_env))?;
+        Ok((access_key, secret_key))
+    }
+
+    fn scope(&self, date: &str) -> String {
+        format!("{}/{}/s3/aws4_request", &date[..8], self.region)
+    }
+
+    fn signature(&self, secret_key: &str, date: &str, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            date,
+            self.scope(date),
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = hmac(format!("AWS4{}", secret_key).as_bytes(), &date[..8]);
+        let key = hmac(&key, &self.region);
+        let key = hmac(&key, "s3");
+        let key = hmac(&key, "aws4_request");
+        hex::encode(hmac(&key, &string_to_sign))
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        if key.is_empty() {
+            format!("/{}", self.bucket)
+        } else {
+            format!("/{}/{}", self.bucket, encode(key, PATH_SET))
+        }
+    }
+
+    /// Builds a signed request to the object at `key`, the bucket itself if empty.
+    fn request(
+        &self,
+        method: &str,
+        key: &str,
+        query: &[(&str, &str)],
+        headers: &[(&str, &str)],
+    ) -> Result<ureq::Request> {
+        let (access_key, secret_key) = self.credentials()?;
+        let date = amz_date(SystemTime::now());
+        let path = self.object_path(key);
+
+        let mut query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", encode(k, QUERY_SET), encode(v, QUERY_SET)))
+            .collect::<Vec<_>>();
+        query.sort();
+        let query = query.join("&");
+
+        let mut headers = headers
+            .iter()
+            .map(|(k, v)| (k.to_lowercase(), v.trim().to_string()))
+            .collect::<Vec<_>>();
+        headers.push((String::from("host"), self.host.clone()));
+        headers.push((
+            String::from("x-amz-content-sha256"),
+            String::from(UNSIGNED_PAYLOAD),
+        ));
+        headers.push((String::from("x-amz-date"), date.clone()));
+        headers.sort();
+
+        let canonical_headers = headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v))
+            .collect::<String>();
+        let signed_headers = headers
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, query, canonical_headers, signed_headers, UNSIGNED_PAYLOAD
+        );
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            access_key,
+            self.scope(&date),
+            signed_headers,
+            self.signature(&secret_key, &date, &canonical_request)
+        );
+
+        let url = if query.is_empty() {
+            format!("{}{}", self.endpoint, path)
+        } else {
+            format!("{}{}?{}", self.endpoint, path, query)
+        };
+
+        let mut req = self.agent.request(method, &url);
+        for (k, v) in headers.iter() {
+            req = req.set(k, v);
+        }
+        Ok(req.set("Authorization", &authorization))
+    }
+
+    /// Builds a GET URL to the object that can be used without credentials until it expires.
+    /// An empty key with a listing query gives a URL to the listing instead.
+    fn presign(&self, key: &str, extra_query: &[(&str, &str)]) -> Result<String> {
+        let (access_key, secret_key) = self.credentials()?;
+        let date = amz_date(SystemTime::now());
+        let path = self.object_path(key);
+
+        let credential = format!("{}/{}", access_key, self.scope(&date));
+        let expiry = self.url_expiry.to_string();
+        let mut query = [
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256"),
+            ("X-Amz-Credential", credential.as_str()),
+            ("X-Amz-Date", date.as_str()),
+            ("X-Amz-Expires", expiry.as_str()),
+            ("X-Amz-SignedHeaders", "host"),
+        ]
+        .iter()
+        .chain(extra_query.iter())
+        .map(|(k, v)| format!("{}={}", encode(k, QUERY_SET), encode(v, QUERY_SET)))
+        .collect::<Vec<_>>();
+        query.sort();
+        let query = query.join("&");
+
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\n{}",
+            path, query, self.host, UNSIGNED_PAYLOAD
+        );
+
+        Ok(format!(
+            "{}{}?{}&X-Amz-Signature={}",
+            self.endpoint,
+            path,
+            query,
+            self.signature(&secret_key, &date, &canonical_request)
+        ))
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<Object>> {
+        lazy_static! {
+            static ref CONTENTS_RE: Regex = Regex::new(r"(?s)<Contents>(.*?)</Contents>").unwrap();
+        }
+
+        let mut objects = Vec::new();
+        let mut token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(t) = &token {
+                query.push(("continuation-token", t));
+            }
+
+            let body = check(self.request("GET", "", &query, &[])?.call())?.into_string()?;
+
+            for cap in CONTENTS_RE.captures_iter(&body) {
+                let key = xml_value(&cap[1], "Key")
+                    .ok_or_else(|| anyhow!("invalid s3 listing: missing key"))?;
+                let size = xml_value(&cap[1], "Size")
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(0);
+                objects.push(Object {
+                    key,
+                    size,
+                    etag: xml_value(&cap[1], "ETag").unwrap_or_default(),
+                    last_modified: xml_value(&cap[1], "LastModified").unwrap_or_default(),
+                });
+            }
+
+            token = match xml_value(&body, "IsTruncated").as_deref() {
+                Some("true") => xml_value(&body, "NextContinuationToken"),
+                _ => None,
+            };
+
+            if token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    fn upload(&self, key: &str, path: &Path) -> Result<()> {
+        let file = fs::File::open(path)?;
+        let size = file.metadata()?.len().to_string();
+        check(
+            self.request("PUT", key, &[], &[])?
+                .set("Content-Length", &size)
+                .send(file),
+        )?;
+        Ok(())
+    }
+
+    fn download(&self, key: &str, dst: &Path) -> Result<()> {
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let resp = check(self.request("GET", key, &[], &[])?.call())?;
+        let partial = dst.with_extension("partial");
+        std::io::copy(&mut resp.into_reader(), &mut fs::File::create(&partial)?)?;
+        fs::rename(partial, dst)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        check(self.request("DELETE", key, &[], &[])?.call())?;
+        Ok(())
+    }
+
+    /// Moves every object under `old_prefix` to `new_prefix`.
+    /// S3 has no rename, so objects are copied then deleted.
+    fn move_prefix(&self, old_prefix: &str, new_prefix: &str) -> Result<()> {
+        for object in self.list(old_prefix)? {
+            let new_key = format!("{}{}", new_prefix, &object.key[old_prefix.len()..]);
+            let source = self.object_path(&object.key);
+            check(
+                self.request("PUT", &new_key, &[], &[("x-amz-copy-source", &source)])?
+                    .call(),
+            )?;
+            self.delete(&object.key)?;
+        }
+        Ok(())
+    }
+
+    fn category_prefix(&self, category: usize, index: &Index) -> Result<String> {
+        let area = index
+            .get_area_from_category(category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        Ok(format!("{}{}/{}/", self.prefix, area, category))
+    }
+
+    fn item_prefix(&self, item: &Item, index: &Index) -> Result<String> {
+        Ok(format!(
+            "{}{}/",
+            self.category_prefix(item.id.category, index)?,
+            item
+        ))
+    }
+
+    fn cached_path(&self, item: &Item) -> Option<PathBuf> {
+        self.cache_dir
+            .as_ref()
+            .map(|cache| cache.join(format!("{}", item)))
+    }
+
+    fn sync_manifest_path(&self, item: &Item) -> Option<PathBuf> {
+        self.cache_dir
+            .as_ref()
+            .map(|cache| cache.join(format!(".{}.sync.json", item)))
+    }
+
+    /// Fetches the objects that are missing from the cache or changed since the last sync, and
+    /// drops the synced files whose object is gone.
+    fn sync(&self, item: &Item, prefix: &str, objects: &[Object], cached: &Path) -> Result<()> {
+        let manifest_path = self
+            .sync_manifest_path(item)
+            .ok_or_else(|| anyhow!("missing cache dir"))?;
+        let previous: SyncManifest = if manifest_path.exists() {
+            // A corrupted manifest only costs a full download.
+            serde_json::from_slice(&fs::read(&manifest_path)?).unwrap_or_default()
+        } else {
+            SyncManifest::new()
+        };
+
+        let mut manifest = SyncManifest::new();
+        for object in objects.iter() {
+            let relative = &object.key[prefix.len()..];
+            let dst = cached.join(cache_relative(relative)?);
+            let version = object.version();
+
+            let up_to_date = previous.get(relative) == Some(&version)
+                && fs::metadata(&dst)
+                    .map(|m| m.len() == object.size)
+                    .unwrap_or(false);
+            if !up_to_date {
+                self.download(&object.key, &dst)?;
+            }
+            manifest.insert(String::from(relative), version);
+        }
+
+        for relative in previous.keys() {
+            if manifest.contains_key(relative) {
+                continue;
+            }
+            if let Ok(stale) = cache_relative(relative).map(|r| cached.join(r)) {
+                if stale.is_file() {
+                    fs::remove_file(stale)?;
+                }
+            }
+        }
+
+        fs::write(&manifest_path, serde_json::to_vec(&manifest)?)?;
+        Ok(())
+    }
+
+    fn drop_cached(&self, item: &Item) -> Result<()> {
+        if let Some(cached) = self.cached_path(item) {
+            if cached.exists() {
+                fs::remove_dir_all(cached)?;
+            }
+        }
+
+        if let Some(manifest) = self.sync_manifest_path(item) {
+            if manifest.exists() {
+                fs::remove_file(manifest)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl LocationResolver for S3Resolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        let prefix = self.item_prefix(item, index)?;
+        let objects = self.list(&prefix)?;
+        if objects.is_empty() {
+            return Ok(None);
+        }
+
+        match self.cached_path(item) {
+            Some(cached) => {
+                self.sync(item, &prefix, &objects, &cached)?;
+                Ok(Some(Location::Path(cached)))
+            }
+            None if objects.len() == 1 => {
+                Ok(Some(Location::URL(self.presign(&objects[0].key, &[])?)))
+            }
+            // Several objects can't be handed out as one, the listing leads to each of them.
+            None => Ok(Some(Location::URL(
+                self.presign("", &[("list-type", "2"), ("prefix", &prefix)])?,
+            ))),
+        }
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        lazy_static! {
+            static ref AREA_RE: Regex = Regex::new(r"^(\d\d)-(\d\d) (.*)$").unwrap();
+            static ref CATEGORY_RE: Regex = Regex::new(r"^(\d\d) (.*)$").unwrap();
+            static ref ITEM_RE: Regex = Regex::new(r"^(\d\d)\.(\d\d\d) (.*)$").unwrap();
+        }
+
+        let mut seen = HashSet::new();
+        for object in self.list(&self.prefix)? {
+            let parts = object.key[self.prefix.len()..]
+                .splitn(4, '/')
+                .collect::<Vec<_>>();
+            if parts.len() < 4 {
+                continue;
+            }
+
+            let (area_cap, category_cap, item_cap) = match (
+                AREA_RE.captures(parts[0]),
+                CATEGORY_RE.captures(parts[1]),
+                ITEM_RE.captures(parts[2]),
+            ) {
+                (Some(a), Some(c), Some(i)) => (a, c, i),
+                _ => continue,
+            };
+
+            let bounds = (area_cap[1].parse::<usize>()?, area_cap[2].parse::<usize>()?);
+            let category_id = category_cap[1].parse::<usize>()?;
+            let id = ID {
+                category: item_cap[1].parse::<usize>()?,
+                id: item_cap[2].parse::<usize>()?,
+            };
+
+            if !self.constraint.matches(category_id) || !seen.insert((id.category, id.id)) {
+                continue;
+            }
+
+            if index.get_area(bounds)?.is_none() {
+                index.create_area(bounds, &area_cap[3])?;
+            }
+
+            let area = index
+                .get_area_mut(bounds)?
+                .ok_or_else(|| anyhow!("missing area"))?;
+
+            if area.get_category(category_id)?.is_none() {
+                area.create_category(category_id, String::from(&category_cap[2]))?;
+            }
+
+            let category = area
+                .get_category_mut(category_id)?
+                .ok_or_else(|| anyhow!("missing category"))?;
+
+            if category.get_item(&id)?.is_none() {
+                category.import_item(Item {
+                    id,
+                    name: String::from(&item_cap[3]),
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let src = match src_location {
+            Location::Path(p) => p,
+            Location::URL(u) => bail!("cannot upload a URL: {}", u),
+        };
+
+        let prefix = self.item_prefix(item, index)?;
+
+        let mut files = Vec::new();
+        if src.is_dir() {
+            list_files(&src, &src, &mut files)?;
+        } else {
+            let name = src
+                .file_name()
+                .ok_or_else(|| anyhow!("invalid source: {:?}", src))?
+                .to_string_lossy()
+                .to_string();
+            files.push((name, src.clone()));
+        }
+
+        for (relative, path) in files {
+            self.upload(&format!("{}{}", prefix, relative), &path)?;
+        }
+
+        if src.is_dir() {
+            fs::remove_dir_all(&src)?;
+        } else {
+            fs::remove_file(&src)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        for object in self.list(&self.item_prefix(id, index)?)? {
+            self.delete(&object.key)?;
+        }
+
+        self.drop_cached(id)
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        let area = index
+            .get_area_from_category(category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let new_prefix = format!("{}{}/{:02} {}/", self.prefix, area, category, new_name);
+        self.move_prefix(&self.category_prefix(category, index)?, &new_prefix)
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        ensure!(old_item.id.category == new_item.id.category);
+        self.move_prefix(
+            &self.item_prefix(old_item, index)?,
+            &self.item_prefix(new_item, index)?,
+        )?;
+
+        self.drop_cached(old_item)
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+use super::{Checkout, DiskResolver, Location, LocationResolver};
+use crate::{Index, Item};
+
+const BLOB_DIR: &str = ".blobs";
+const MANIFEST_FILE: &str = ".jd-manifest.json";
+
+/// A piece of content materialized more than once in the store.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Duplicate {
+    pub hash: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Maps the relative path of every file in an item to the hash of its content.
+type Manifest = BTreeMap<String, String>;
+
+fn hash_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn partial_path(path: &Path) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid path: {:?}", path))?
+        .to_string_lossy();
+    Ok(path.with_file_name(format!("{}.partial", name)))
+}
+
+fn list_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    for entry in fs::read_dir(dir)?.filter_map(|f| f.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else if entry.file_name() != MANIFEST_FILE {
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Clones the file's extents on filesystems that support it (btrfs, xfs), the copy shares
+/// storage with the blob until either side is written to.
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dst: &Path) -> Result<()> {
+    use nix::libc;
+    use std::os::unix::io::AsRawFd;
+
+    let src_file = fs::File::open(src)?;
+    let dst_file = fs::File::create(dst)?;
+    let cloned =
+        unsafe { libc::ioctl(dst_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) } == 0;
+    if !cloned {
+        let error = io::Error::last_os_error();
+        drop(dst_file);
+        fs::remove_file(dst)?;
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_src: &Path, _dst: &Path) -> Result<()> {
+    bail!("reflinks are not supported on this platform");
+}
+
+fn set_readonly(path: &Path, readonly: bool) -> Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(readonly);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+/// Stores file content in a hash-addressed blob store, and materializes items as reflinks of
+/// their blobs, or as read-only hard links where the filesystem can't clone files.
+pub struct StoreResolver {
+    layout: DiskResolver,
+    root_path: PathBuf,
+}
+
+impl StoreResolver {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            layout: DiskResolver::new(root_path.clone()),
+            root_path,
+        }
+    }
+
+    fn item_path(&self, item: &Item, index: &Index) -> Result<PathBuf> {
+        let category_path = self.layout.get_category_path(item.id.category, index)?;
+        Ok(category_path.join(format!("{}", item)))
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.root_path
+            .join(BLOB_DIR)
+            .join(&hash[..2])
+            .join(&hash[2..])
+    }
+
+    /// Adds the file to the blob store if its content isn't there yet, and returns its hash.
+    fn store_blob(&self, path: &Path) -> Result<String> {
+        let hash = hash_file(path)?;
+        let blob = self.blob_path(&hash);
+
+        if !blob.exists() {
+            let parent = blob.parent().ok_or_else(|| anyhow!("invalid blob path"))?;
+            fs::create_dir_all(parent)?;
+
+            // Blobs are read-only, so a hard link to one can't be edited in place.
+            let partial = blob.with_extension("partial");
+            fs::copy(path, &partial)?;
+            set_readonly(&partial, true)?;
+            fs::rename(partial, &blob)?;
+        }
+
+        Ok(hash)
+    }
+
+    fn materialize(&self, hash: &str, dst: &Path) -> Result<()> {
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let blob = self.blob_path(hash);
+        if reflink(&blob, dst).is_ok() || fs::hard_link(&blob, dst).is_ok() {
+            return Ok(());
+        }
+
+        // The store spans filesystems, only a private copy is left.
+        fs::copy(&blob, dst)?;
+        set_readonly(dst, false)
+    }
+
+    /// Replaces the file with a private, writable copy, so editing it leaves the blob alone.
+    fn unlink(&self, path: &Path) -> Result<()> {
+        if !fs::metadata(path)?.permissions().readonly() {
+            return Ok(());
+        }
+
+        let partial = partial_path(path)?;
+        fs::copy(path, &partial)?;
+        set_readonly(&partial, false)?;
+        fs::rename(partial, path)?;
+        Ok(())
+    }
+
+    /// Materializes the item's files again, so content that is back in the store shares it.
+    fn relink(&self, item_path: &Path, manifest: &Manifest) -> Result<()> {
+        for (relative, hash) in manifest {
+            let path = item_path.join(relative);
+            let partial = partial_path(&path)?;
+            self.materialize(hash, &partial)?;
+            fs::rename(partial, path)?;
+        }
+        Ok(())
+    }
+
+    fn read_manifest(&self, item_path: &Path) -> Result<Manifest> {
+        Ok(serde_json::from_reader(fs::File::open(
+            item_path.join(MANIFEST_FILE),
+        )?)?)
+    }
+
+    /// Stores the current content of the item's files and records it in its manifest.
+    fn sync_manifest(&self, item_path: &Path) -> Result<Manifest> {
+        let mut files = Vec::new();
+        list_files(item_path, item_path, &mut files)?;
+
+        let mut manifest = Manifest::new();
+        for (relative, path) in files {
+            manifest.insert(relative, self.store_blob(&path)?);
+        }
+
+        let partial = item_path.join(format!("{}.partial", MANIFEST_FILE));
+        fs::write(&partial, serde_json::to_string_pretty(&manifest)?)?;
+        fs::rename(partial, item_path.join(MANIFEST_FILE))?;
+        Ok(manifest)
+    }
+
+    fn manifests(&self) -> Result<Vec<(PathBuf, Manifest)>> {
+        let mut manifests = Vec::new();
+        if !self.root_path.exists() {
+            return Ok(manifests);
+        }
+
+        for area in fs::read_dir(&self.root_path)?.filter_map(|f| f.ok()) {
+            if area.file_name().to_string_lossy().starts_with('.') || !area.path().is_dir() {
+                continue;
+            }
+
+            for category in fs::read_dir(area.path())?.filter_map(|f| f.ok()) {
+                if !category.path().is_dir() {
+                    continue;
+                }
+
+                for item in fs::read_dir(category.path())?.filter_map(|f| f.ok()) {
+                    let manifest_path = item.path().join(MANIFEST_FILE);
+                    if manifest_path.exists() {
+                        manifests.push((item.path(), self.read_manifest(&item.path())?));
+                    }
+                }
+            }
+        }
+
+        Ok(manifests)
+    }
+}
+
+impl LocationResolver for StoreResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        self.layout.get(item, index)
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        self.layout.collect(index)
+    }
+
+    /// Moves the source content into the blob store, then materializes it in the item folder.
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let src = match src_location {
+            Location::Path(p) => p,
+            Location::URL(u) => bail!("cannot store a URL: {}", u),
+        };
+
+        let dst = self.item_path(item, index)?;
+        ensure!(!dst.exists(), "item folder already exists: {:?}", dst);
+
+        let mut files = Vec::new();
+        if src.is_dir() {
+            list_files(&src, &src, &mut files)?;
+        } else {
+            let name = src
+                .file_name()
+                .ok_or_else(|| anyhow!("invalid source: {:?}", src))?
+                .to_string_lossy()
+                .to_string();
+            files.push((name, src.clone()));
+        }
+
+        fs::create_dir_all(&dst)?;
+
+        let mut manifest = Manifest::new();
+        for (relative, path) in files {
+            let hash = self.store_blob(&path)?;
+            self.materialize(&hash, &dst.join(&relative))?;
+            manifest.insert(relative, hash);
+        }
+
+        fs::write(
+            dst.join(MANIFEST_FILE),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+
+        if src.is_dir() {
+            fs::remove_dir_all(&src)?;
+        } else {
+            fs::remove_file(&src)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the item folder, its blobs are only dropped by `gc`.
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        let path = self.item_path(id, index)?;
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+        Ok(())
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        let old_path = self.layout.get_category_path(category, index)?;
+        let parent = old_path
+            .parent()
+            .ok_or_else(|| anyhow!("invalid category path"))?;
+        let new_path = parent.join(format!("{:02} {}", category, new_name));
+
+        fs::rename(old_path, new_path)?;
+        Ok(())
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        ensure!(old_item.id.category == new_item.id.category);
+        fs::rename(
+            self.item_path(old_item, index)?,
+            self.item_path(new_item, index)?,
+        )?;
+        Ok(())
+    }
+
+    /// Items are materialized as files sharing storage with their blobs, so they can be read in
+    /// place.
+    fn local_path(&self, item: &Item, index: &Index) -> Result<Option<PathBuf>> {
+        self.layout.local_path(item, index)
+    }
+
+    /// Breaks the hard links to the blob store, so the item's files can be edited.
+    fn checkout(&self, item: &Item, index: &Index) -> Result<Option<Checkout>> {
+        let item_path = self.item_path(item, index)?;
+        if item_path.join(MANIFEST_FILE).exists() {
+            for relative in self.read_manifest(&item_path)?.keys() {
+                let path = item_path.join(relative);
+                if path.exists() {
+                    self.unlink(&path)?;
+                }
+            }
+        }
+
+        Ok(self.get(item, index)?.map(|location| Checkout {
+            location,
+            needs_checkin: true,
+        }))
+    }
+
+    /// Records the edits made to the item, so `gc` and `duplicates` see its current content,
+    /// then links its files back to the store.
+    fn checkin(&self, item: &Item, checkout: Checkout, index: &Index) -> Result<()> {
+        let item_path = self.item_path(item, index)?;
+        if checkout.needs_checkin && item_path.join(MANIFEST_FILE).exists() {
+            let manifest = self.sync_manifest(&item_path)?;
+            self.relink(&item_path, &manifest)?;
+        }
+        Ok(())
+    }
+
+    fn gc(&self, _index: &Index) -> Result<Vec<PathBuf>> {
+        let referenced = self
+            .manifests()?
+            .into_iter()
+            .flat_map(|(_, m)| m.into_values())
+            .collect::<HashSet<_>>();
+
+        let mut removed = Vec::new();
+        let blob_root = self.root_path.join(BLOB_DIR);
+        if !blob_root.exists() {
+            return Ok(removed);
+        }
+
+        for prefix in fs::read_dir(&blob_root)?.filter_map(|f| f.ok()) {
+            for blob in fs::read_dir(prefix.path())?.filter_map(|f| f.ok()) {
+                let hash = format!(
+                    "{}{}",
+                    prefix.file_name().to_string_lossy(),
+                    blob.file_name().to_string_lossy()
+                );
+
+                if !referenced.contains(&hash) {
+                    fs::remove_file(blob.path())?;
+                    removed.push(blob.path());
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    fn duplicates(&self, _index: &Index) -> Result<Vec<Duplicate>> {
+        let mut by_hash: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for (item_path, manifest) in self.manifests()? {
+            for (relative, hash) in manifest {
+                by_hash
+                    .entry(hash)
+                    .or_default()
+                    .push(item_path.join(relative));
+            }
+        }
+
+        Ok(by_hash
+            .into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .map(|(hash, files)| Duplicate { hash, files })
+            .collect())
+    }
+}
@@ -0,0 +1,416 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use argon2::Argon2;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use lazy_static::lazy_static;
+
+use regex::Regex;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Checkout, DiskResolver, Location, LocationResolver};
+use crate::{Index, Item};
+
+const CONTAINER_MAGIC: &[u8] = b"JDVAULT1";
+const CONTAINER_EXTENSION: &str = "jdvault";
+const VAULT_FILE: &str = ".vault.json";
+const KEY_CHECK: &[u8] = b"johnny.decimal";
+
+/// Vault-wide parameters, stored in plain text at the vault root.
+#[derive(Deserialize, Serialize)]
+struct VaultInfo {
+    salt: String,
+    check_nonce: String,
+    check: String,
+}
+
+/// Plain text metadata of a container, readable without the key.
+#[derive(Deserialize, Serialize)]
+struct ContainerHeader {
+    item: Item,
+    nonce: String,
+}
+
+fn read_header(file: &mut fs::File) -> Result<ContainerHeader> {
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic)?;
+    ensure!(magic == CONTAINER_MAGIC, "not a jd vault container");
+
+    let mut len = [0u8; 4];
+    file.read_exact(&mut len)?;
+
+    let mut header = vec![0u8; u32::from_le_bytes(len) as usize];
+    file.read_exact(&mut header)?;
+
+    Ok(serde_json::from_slice(&header)?)
+}
+
+fn write_container(path: &Path, header: &ContainerHeader, payload: &[u8]) -> Result<()> {
+    let header = serde_json::to_vec(header)?;
+    let partial = path.with_extension("partial");
+
+    let mut file = fs::File::create(&partial)?;
+    file.write_all(CONTAINER_MAGIC)?;
+    file.write_all(&(header.len() as u32).to_le_bytes())?;
+    file.write_all(&header)?;
+    file.write_all(payload)?;
+    file.sync_all()?;
+
+    fs::rename(partial, path)?;
+    Ok(())
+}
+
+fn pack(src: &Path) -> Result<Vec<u8>> {
+    let encoder = zstd::Encoder::new(Vec::new(), 0)?;
+    let mut tar = tar::Builder::new(encoder);
+    if src.is_dir() {
+        tar.append_dir_all(".", src)?;
+    } else {
+        let name = src
+            .file_name()
+            .ok_or_else(|| anyhow!("invalid source: {:?}", src))?;
+        tar.append_path_with_name(src, name)?;
+    }
+    Ok(tar.into_inner()?.finish()?)
+}
+
+fn unpack(data: &[u8], dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    let decoder = zstd::Decoder::new(data)?;
+    tar::Archive::new(decoder).unpack(dst)?;
+    Ok(())
+}
+
+/// Stores every item in an encrypted container, only item names and IDs are kept in clear.
+pub struct VaultResolver {
+    layout: DiskResolver,
+    root_path: PathBuf,
+    keyfile: Option<PathBuf>,
+    passphrase_env: String,
+    cipher: Mutex<Option<XChaCha20Poly1305>>,
+}
+
+impl VaultResolver {
+    pub fn new(
+        root_path: PathBuf,
+        keyfile: Option<PathBuf>,
+        passphrase_env: Option<String>,
+    ) -> Self {
+        Self {
+            layout: DiskResolver::new(root_path.clone()),
+            root_path,
+            keyfile,
+            passphrase_env: passphrase_env.unwrap_or_else(|| String::from("JD_PASSPHRASE")),
+            cipher: Mutex::new(None),
+        }
+    }
+
+    fn secret(&self) -> Result<Vec<u8>> {
+        if let Some(keyfile) = &self.keyfile {
+            return fs::read(keyfile).with_context(|| format!("failed to read {:?}", keyfile));
+        }
+
+        if let Ok(passphrase) = std::env::var(&self.passphrase_env) {
+            return Ok(passphrase.into_bytes());
+        }
+
+        let passphrase = rpassword::prompt_password(format!(
+            "Passphrase for {}: ",
+            self.root_path.to_string_lossy()
+        ))?;
+        Ok(passphrase.into_bytes())
+    }
+
+    /// Derives the vault key, creating the vault parameters on first use.
+    fn cipher(&self) -> Result<XChaCha20Poly1305> {
+        let mut guard = self
+            .cipher
+            .lock()
+            .map_err(|_| anyhow!("vault key poisoned"))?;
+
+        if let Some(cipher) = guard.as_ref() {
+            return Ok(cipher.clone());
+        }
+
+        let info_path = self.root_path.join(VAULT_FILE);
+        let existing: Option<VaultInfo> = if info_path.exists() {
+            Some(serde_json::from_reader(fs::File::open(&info_path)?)?)
+        } else {
+            None
+        };
+
+        let salt = match &existing {
+            Some(info) => hex::decode(&info.salt)?,
+            None => {
+                let mut salt = vec![0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                salt
+            }
+        };
+
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(&self.secret()?, &salt, &mut key)
+            .map_err(|e| anyhow!("failed to derive key: {}", e))?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+
+        match existing {
+            Some(info) => {
+                let nonce = hex::decode(&info.check_nonce)?;
+                let check = cipher
+                    .decrypt(
+                        XNonce::from_slice(&nonce),
+                        hex::decode(&info.check)?.as_ref(),
+                    )
+                    .map_err(|_| anyhow!("invalid passphrase or keyfile"))?;
+                ensure!(check == KEY_CHECK, "invalid passphrase or keyfile");
+            }
+            None => {
+                let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+                let check = cipher
+                    .encrypt(&nonce, KEY_CHECK)
+                    .map_err(|_| anyhow!("encryption failed"))?;
+                let info = VaultInfo {
+                    salt: hex::encode(&salt),
+                    check_nonce: hex::encode(nonce),
+                    check: hex::encode(check),
+                };
+                fs::create_dir_all(&self.root_path)?;
+                fs::write(&info_path, serde_json::to_string_pretty(&info)?)?;
+            }
+        }
+
+        *guard = Some(cipher.clone());
+        Ok(cipher)
+    }
+
+    fn container_path(&self, item: &Item, index: &Index) -> Result<PathBuf> {
+        // Only the ID ends up in the file name, the item name is kept in the container's clear
+        // header, next to the encrypted content.
+        let category_path = self.layout.get_category_path(item.id.category, index)?;
+        Ok(category_path.join(format!("{}.{}", item.id, CONTAINER_EXTENSION)))
+    }
+
+    fn checkout_path(&self, item: &Item) -> PathBuf {
+        std::env::temp_dir().join(format!("jd-{}-{}", item.id, std::process::id()))
+    }
+
+    fn seal(&self, item: &Item, src: &Path, dst: &Path) -> Result<()> {
+        let cipher = self.cipher()?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = cipher
+            .encrypt(&nonce, pack(src)?.as_ref())
+            .map_err(|_| anyhow!("encryption failed"))?;
+
+        let header = ContainerHeader {
+            item: item.clone(),
+            nonce: hex::encode(nonce),
+        };
+        write_container(dst, &header, &payload)
+    }
+
+    fn open_container(&self, path: &Path, dst: &Path) -> Result<()> {
+        let mut file = fs::File::open(path)?;
+        let header = read_header(&mut file)?;
+
+        let mut payload = Vec::new();
+        file.read_to_end(&mut payload)?;
+
+        let plain = self
+            .cipher()?
+            .decrypt(
+                XNonce::from_slice(&hex::decode(&header.nonce)?),
+                payload.as_ref(),
+            )
+            .map_err(|_| anyhow!("failed to decrypt {:?}", path))?;
+
+        unpack(&plain, dst)
+    }
+
+    /// Rewrites the clear metadata of a container without touching its payload.
+    fn relabel(&self, src: &Path, dst: &Path, item: &Item) -> Result<()> {
+        let mut file = fs::File::open(src)?;
+        let mut header = read_header(&mut file)?;
+        let mut payload = Vec::new();
+        file.read_to_end(&mut payload)?;
+
+        header.item = item.clone();
+        write_container(dst, &header, &payload)?;
+
+        if src != dst {
+            fs::remove_file(src)?;
+        }
+        Ok(())
+    }
+
+    fn collect_category(&self, path: &Path) -> Result<Vec<Item>> {
+        let mut items = Vec::new();
+        for entry in fs::read_dir(path)?.filter_map(|f| f.ok()) {
+            let is_container = entry
+                .path()
+                .extension()
+                .map(|e| e == CONTAINER_EXTENSION)
+                .unwrap_or(false);
+
+            if is_container {
+                items.push(read_header(&mut fs::File::open(entry.path())?)?.item);
+            }
+        }
+        Ok(items)
+    }
+}
+
+impl LocationResolver for VaultResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        let path = self.container_path(item, index)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(Location::Path(path)))
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        lazy_static! {
+            static ref AREA_RE: Regex = Regex::new(r"^(\d\d)-(\d\d) (.*)$").unwrap();
+            static ref CATEGORY_RE: Regex = Regex::new(r"^(\d\d) (.*)$").unwrap();
+        }
+
+        for area_entry in fs::read_dir(&self.root_path)?.filter_map(|f| f.ok()) {
+            let area_name = area_entry.file_name().to_string_lossy().to_string();
+            let cap = match AREA_RE.captures(&area_name) {
+                Some(c) if area_entry.path().is_dir() => c,
+                _ => continue,
+            };
+            let bounds = (cap[1].parse::<usize>()?, cap[2].parse::<usize>()?);
+
+            if index.get_area(bounds)?.is_none() {
+                index.create_area(bounds, &cap[3])?;
+            }
+
+            let area = index
+                .get_area_mut(bounds)?
+                .ok_or_else(|| anyhow!("missing area"))?;
+
+            for category_entry in fs::read_dir(area_entry.path())?.filter_map(|f| f.ok()) {
+                let category_name = category_entry.file_name().to_string_lossy().to_string();
+                let cat_cap = match CATEGORY_RE.captures(&category_name) {
+                    Some(c) if category_entry.path().is_dir() => c,
+                    _ => continue,
+                };
+                let category_id = cat_cap[1].parse::<usize>()?;
+
+                if area.get_category(category_id)?.is_none() {
+                    area.create_category(category_id, String::from(&cat_cap[2]))?;
+                }
+
+                let category = area
+                    .get_category_mut(category_id)?
+                    .ok_or_else(|| anyhow!("missing category"))?;
+
+                for item in self.collect_category(&category_entry.path())? {
+                    category.import_item(item)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let src = match src_location {
+            Location::Path(p) => p,
+            Location::URL(u) => bail!("cannot encrypt a URL: {}", u),
+        };
+
+        let dst = self.container_path(item, index)?;
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let is_container = src
+            .extension()
+            .map(|e| e == CONTAINER_EXTENSION)
+            .unwrap_or(false);
+
+        if src.is_file() && is_container {
+            // Already encrypted, only the metadata changes.
+            return self.relabel(&src, &dst, item);
+        }
+
+        self.seal(item, &src, &dst)?;
+        if src.is_dir() {
+            fs::remove_dir_all(&src)?;
+        } else {
+            fs::remove_file(&src)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        let path = self.container_path(id, index)?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        self.layout.rename_category(category, new_name, index)
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        ensure!(old_item.id.category == new_item.id.category);
+        let old_path = self.container_path(old_item, index)?;
+        let new_path = self.container_path(new_item, index)?;
+        self.relabel(&old_path, &new_path, new_item)
+    }
+
+    fn checkout(&self, item: &Item, index: &Index) -> Result<Option<Checkout>> {
+        let container = self.container_path(item, index)?;
+        if !container.exists() {
+            return Ok(None);
+        }
+
+        let dst = self.checkout_path(item);
+        if dst.exists() {
+            fs::remove_dir_all(&dst)?;
+        }
+
+        // Created private, so the decrypted content is never readable by other users.
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&dst)?;
+
+        self.open_container(&container, &dst)?;
+
+        Ok(Some(Checkout {
+            location: Location::Path(dst),
+            needs_checkin: true,
+        }))
+    }
+
+    fn checkin(&self, item: &Item, checkout: Checkout, index: &Index) -> Result<()> {
+        let src = match checkout.location {
+            Location::Path(p) => p,
+            Location::URL(u) => bail!("incoherent location: {}", u),
+        };
+
+        self.seal(item, &src, &self.container_path(item, index)?)?;
+        fs::remove_dir_all(&src)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,452 @@
+//! Resolver storing items on a WebDAV share, such as a Nextcloud instance.
+//!
+//! The share mirrors the `DiskResolver` tree: areas and categories are collections, and every
+//! item is a collection holding the files that were filed under it.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+
+use base64::Engine;
+
+use lazy_static::lazy_static;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use regex::Regex;
+
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+use super::{ContentHashes, Location, LocationResolver};
+use crate::{Category, Index, Item, ResolverConstraint, ID};
+
+const SEGMENT_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WebDavConfig {
+    /// URL of the collection holding the tree,
+    /// e.g. `https://cloud.example.com/remote.php/dav/files/<user>/Documents`.
+    pub webdav_url: String,
+
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// Environment variables read when the credentials are not in the config.
+    /// Default to `JD_WEBDAV_USERNAME` and `JD_WEBDAV_PASSWORD`.
+    #[serde(default)]
+    pub username_env: Option<String>,
+    #[serde(default)]
+    pub password_env: Option<String>,
+}
+
+/// Extracts the decoded path of a URL or an absolute path, without its trailing slash.
+fn url_path(href: &str) -> String {
+    let path = match href.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or(""),
+        None => href,
+    };
+    percent_decode_str(path.trim_end_matches('/'))
+        .decode_utf8_lossy()
+        .to_string()
+}
+
+struct Entry {
+    name: String,
+    is_collection: bool,
+}
+
+/// Stores items as collections on a WebDAV share.
+pub struct WebDavResolver {
+    agent: ureq::Agent,
+    base_url: String,
+    auth: Option<String>,
+    constraint: ResolverConstraint,
+}
+
+impl WebDavResolver {
+    pub fn new(config: &WebDavConfig, constraint: ResolverConstraint) -> Result<Self> {
+        let from_env = |var: &Option<String>, default: &str| {
+            std::env::var(var.as_deref().unwrap_or(default)).ok()
+        };
+
+        let username = config
+            .username
+            .clone()
+            .or_else(|| from_env(&config.username_env, "JD_WEBDAV_USERNAME"));
+        let password = config
+            .password
+            .clone()
+            .or_else(|| from_env(&config.password_env, "JD_WEBDAV_PASSWORD"));
+
+        let auth = match (username, password) {
+            (Some(u), Some(p)) => Some(format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", u, p))
+            )),
+            (None, None) => None,
+            _ => bail!("webdav credentials need both a username and a password"),
+        };
+
+        Ok(Self {
+            agent: ureq::AgentBuilder::new().build(),
+            base_url: config.webdav_url.trim_end_matches('/').to_string(),
+            auth,
+            constraint,
+        })
+    }
+
+    fn url(&self, segments: &[String]) -> String {
+        let mut url = self.base_url.clone();
+        for segment in segments {
+            url.push('/');
+            url.push_str(&utf8_percent_encode(segment, SEGMENT_SET).to_string());
+        }
+        url
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let req = self.agent.request(method, url);
+        match &self.auth {
+            Some(auth) => req.set("Authorization", auth),
+            None => req,
+        }
+    }
+
+    /// Sends the request, mapping the given status codes to `None`.
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        result: Result<ureq::Response, ureq::Error>,
+        allowed: &[u16],
+    ) -> Result<Option<ureq::Response>> {
+        match result {
+            Ok(r) => Ok(Some(r)),
+            Err(ureq::Error::Status(code, _)) if allowed.contains(&code) => Ok(None),
+            Err(ureq::Error::Status(code, resp)) => {
+                bail!(
+                    "webdav error ({} {}) on {} {}",
+                    code,
+                    resp.status_text(),
+                    method,
+                    url
+                )
+            }
+            Err(e) => Err(e).with_context(|| format!("webdav request failed: {} {}", method, url)),
+        }
+    }
+
+    /// Whether a resource exists at the URL, without listing its children.
+    fn exists(&self, url: &str) -> Result<bool> {
+        let result = self
+            .request("PROPFIND", url)
+            .set("Depth", "0")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(PROPFIND_BODY);
+
+        Ok(self.send("PROPFIND", url, result, &[404])?.is_some())
+    }
+
+    /// Lists the direct children of a collection, an empty list if it doesn't exist.
+    fn list(&self, segments: &[String]) -> Result<Vec<Entry>> {
+        lazy_static! {
+            static ref RESPONSE_RE: Regex =
+                Regex::new(r"(?s)<(?:[\w-]+:)?response\b.*?</(?:[\w-]+:)?response>").unwrap();
+            static ref HREF_RE: Regex =
+                Regex::new(r"(?s)<(?:[\w-]+:)?href>(.*?)</(?:[\w-]+:)?href>").unwrap();
+            static ref COLLECTION_RE: Regex = Regex::new(r"<(?:[\w-]+:)?collection\s*/?>").unwrap();
+        }
+
+        let url = format!("{}/", self.url(segments));
+        let result = self
+            .request("PROPFIND", &url)
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(PROPFIND_BODY);
+
+        let body = match self.send("PROPFIND", &url, result, &[404])? {
+            Some(r) => r.into_string()?,
+            None => return Ok(Vec::new()),
+        };
+
+        let own_path = url_path(&url);
+        let mut entries = Vec::new();
+        for response in RESPONSE_RE.find_iter(&body) {
+            let href = match HREF_RE.captures(response.as_str()) {
+                Some(cap) => url_path(&cap[1].trim().replace("&amp;", "&")),
+                None => continue,
+            };
+
+            // The collection itself is part of the listing.
+            if href == own_path {
+                continue;
+            }
+
+            let name = String::from(href.rsplit('/').next().unwrap_or(""));
+            entries.push(Entry {
+                name,
+                is_collection: COLLECTION_RE.is_match(response.as_str()),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn mkcol(&self, segments: &[String]) -> Result<()> {
+        let url = self.url(segments);
+        let result = self.request("MKCOL", &url).call();
+        // 405 means the collection already exists.
+        self.send("MKCOL", &url, result, &[405])?;
+        Ok(())
+    }
+
+    fn put(&self, segments: &[String], path: &Path) -> Result<()> {
+        let url = self.url(segments);
+        let file = fs::File::open(path)?;
+        let size = file.metadata()?.len().to_string();
+        let result = self
+            .request("PUT", &url)
+            .set("Content-Length", &size)
+            .send(file);
+        self.send("PUT", &url, result, &[])?;
+        Ok(())
+    }
+
+    fn upload(&self, segments: &mut Vec<String>, path: &Path) -> Result<()> {
+        if path.is_dir() {
+            self.mkcol(segments)?;
+            for entry in fs::read_dir(path)?.filter_map(|f| f.ok()) {
+                segments.push(entry.file_name().to_string_lossy().to_string());
+                self.upload(segments, &entry.path())?;
+                segments.pop();
+            }
+            Ok(())
+        } else {
+            self.put(segments, path)
+        }
+    }
+
+    /// Hashes every file under the collection, walking it through its listings.
+    fn hash_tree(
+        &self,
+        segments: &mut Vec<String>,
+        prefix: &str,
+        hashes: &mut ContentHashes,
+    ) -> Result<()> {
+        for entry in self.list(segments)? {
+            let relative = format!("{}{}", prefix, entry.name);
+            segments.push(entry.name);
+
+            if entry.is_collection {
+                self.hash_tree(segments, &format!("{}/", relative), hashes)?;
+            } else {
+                let url = self.url(segments);
+                let result = self.request("GET", &url).call();
+                let response = self
+                    .send("GET", &url, result, &[])?
+                    .ok_or_else(|| anyhow!("webdav returned no content for {}", url))?;
+
+                let mut hasher = Sha256::new();
+                io::copy(&mut response.into_reader(), &mut hasher)?;
+                hashes.insert(relative, format!("{:x}", hasher.finalize()));
+            }
+
+            segments.pop();
+        }
+        Ok(())
+    }
+
+    fn move_to(&self, from: &[String], to: &[String]) -> Result<()> {
+        let url = self.url(from);
+        let result = self
+            .request("MOVE", &url)
+            .set("Destination", &self.url(to))
+            .set("Overwrite", "F")
+            .call();
+        self.send("MOVE", &url, result, &[])?;
+        Ok(())
+    }
+
+    fn category_segments(&self, category: usize, index: &Index) -> Result<Vec<String>> {
+        let area = index
+            .get_area_from_category(category)?
+            .ok_or_else(|| anyhow!("missing area"))?;
+
+        let category = area
+            .get_category(category)?
+            .ok_or_else(|| anyhow!("missing category"))?;
+
+        Ok(vec![format!("{}", area), format!("{}", category)])
+    }
+
+    fn item_segments(&self, item: &Item, index: &Index) -> Result<Vec<String>> {
+        let mut segments = self.category_segments(item.id.category, index)?;
+        segments.push(format!("{}", item));
+        Ok(segments)
+    }
+
+    fn collect_category(&self, segments: &[String], category: &mut Category) -> Result<()> {
+        lazy_static! {
+            static ref ITEM_RE: Regex = Regex::new(r"^(\d\d)\.(\d\d\d) (.*)$").unwrap();
+        }
+
+        for entry in self.list(segments)? {
+            let cap = match ITEM_RE.captures(&entry.name) {
+                Some(c) if entry.is_collection => c,
+                _ => continue,
+            };
+
+            let id = ID {
+                category: cap[1].parse::<usize>()?,
+                id: cap[2].parse::<usize>()?,
+            };
+
+            if id.category == category.id && category.get_item(&id)?.is_none() {
+                category.import_item(Item {
+                    id,
+                    name: String::from(&cap[3]),
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl LocationResolver for WebDavResolver {
+    fn get(&self, item: &Item, index: &Index) -> Result<Option<Location>> {
+        let url = format!("{}/", self.url(&self.item_segments(item, index)?));
+        if !self.exists(&url)? {
+            return Ok(None);
+        }
+        Ok(Some(Location::URL(url)))
+    }
+
+    fn collect(&self, index: &mut Index) -> Result<()> {
+        lazy_static! {
+            static ref AREA_RE: Regex = Regex::new(r"^(\d\d)-(\d\d) (.*)$").unwrap();
+            static ref CATEGORY_RE: Regex = Regex::new(r"^(\d\d) (.*)$").unwrap();
+        }
+
+        for area_entry in self.list(&[])? {
+            let area_cap = match AREA_RE.captures(&area_entry.name) {
+                Some(c) if area_entry.is_collection => c,
+                _ => continue,
+            };
+
+            let bounds = (area_cap[1].parse::<usize>()?, area_cap[2].parse::<usize>()?);
+            ensure!(
+                bounds.0 + 9 == bounds.1,
+                "invalid collection bounds: {}-{}",
+                bounds.0,
+                bounds.1
+            );
+
+            let area_segments = vec![area_entry.name.clone()];
+            for category_entry in self.list(&area_segments)? {
+                let category_cap = match CATEGORY_RE.captures(&category_entry.name) {
+                    Some(c) if category_entry.is_collection => c,
+                    _ => continue,
+                };
+
+                let category_id = category_cap[1].parse::<usize>()?;
+                if !self.constraint.matches(category_id) {
+                    continue;
+                }
+
+                if index.get_area(bounds)?.is_none() {
+                    index.create_area(bounds, &area_cap[3])?;
+                }
+
+                let area = index
+                    .get_area_mut(bounds)?
+                    .ok_or_else(|| anyhow!("missing area"))?;
+
+                if area.get_category(category_id)?.is_none() {
+                    area.create_category(category_id, String::from(&category_cap[2]))?;
+                }
+
+                let category = area
+                    .get_category_mut(category_id)?
+                    .ok_or_else(|| anyhow!("missing category"))?;
+
+                let category_segments = vec![area_entry.name.clone(), category_entry.name.clone()];
+                self.collect_category(&category_segments, category)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, item: &Item, src_location: Location, index: &Index) -> Result<()> {
+        let src = match src_location {
+            Location::Path(p) => p,
+            Location::URL(u) => bail!("cannot upload a URL: {}", u),
+        };
+
+        let mut segments = self.item_segments(item, index)?;
+        for depth in 1..=segments.len() {
+            self.mkcol(&segments[..depth])?;
+        }
+
+        if src.is_dir() {
+            for entry in fs::read_dir(&src)?.filter_map(|f| f.ok()) {
+                segments.push(entry.file_name().to_string_lossy().to_string());
+                self.upload(&mut segments, &entry.path())?;
+                segments.pop();
+            }
+            fs::remove_dir_all(&src)?;
+        } else {
+            let name = src
+                .file_name()
+                .ok_or_else(|| anyhow!("invalid source: {:?}", src))?
+                .to_string_lossy()
+                .to_string();
+            segments.push(name);
+            self.put(&segments, &src)?;
+            fs::remove_file(&src)?;
+        }
+
+        Ok(())
+    }
+
+    /// Items are only reachable through their URL, so their files are read back from the share.
+    fn remote_hashes(&self, item: &Item, index: &Index) -> Result<Option<ContentHashes>> {
+        let mut hashes = ContentHashes::new();
+        self.hash_tree(&mut self.item_segments(item, index)?, "", &mut hashes)?;
+        Ok(Some(hashes))
+    }
+
+    fn remove(&self, id: &Item, index: &Index) -> Result<()> {
+        let url = format!("{}/", self.url(&self.item_segments(id, index)?));
+        let result = self.request("DELETE", &url).call();
+        self.send("DELETE", &url, result, &[404])?;
+        Ok(())
+    }
+
+    fn rename_category(&self, category: usize, new_name: &str, index: &Index) -> Result<()> {
+        let old = self.category_segments(category, index)?;
+        let new = vec![old[0].clone(), format!("{:02} {}", category, new_name)];
+        self.move_to(&old, &new)
+    }
+
+    fn rename_item(&self, old_item: &Item, new_item: &Item, index: &Index) -> Result<()> {
+        ensure!(old_item.id.category == new_item.id.category);
+        self.move_to(
+            &self.item_segments(old_item, index)?,
+            &self.item_segments(new_item, index)?,
+        )
+    }
+}
@@ -0,0 +1,345 @@
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+
+use regex::{Regex, RegexBuilder};
+
+use serde::Serialize;
+
+use crate::{Area, Category, Item};
+
+/// Score of every matched character.
+const MATCH: i64 = 16;
+
+/// Bonus for a match on the first character of the name.
+const PREFIX: i64 = 24;
+
+/// Bonus for a match at the start of a word, which is what makes acronyms rank well.
+const BOUNDARY: i64 = 16;
+
+/// Bonus for a match right after the previous one.
+const CONSECUTIVE: i64 = 12;
+
+/// Penalty for every character skipped between two matches.
+const GAP: i64 = 1;
+
+/// Cap on the penalty for the characters skipped before the first match.
+const MAX_LEADING_GAP: i64 = 8;
+
+const NONE: i64 = i64::MIN / 2;
+
+/// The kinds of entries returned by a search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HitKind {
+    Area,
+    Category,
+    Item,
+}
+
+impl FromStr for HitKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "area" => Ok(HitKind::Area),
+            "cat" | "category" => Ok(HitKind::Category),
+            "item" => Ok(HitKind::Item),
+            _ => bail!("invalid kind: {}, expected area, category or item", s),
+        }
+    }
+}
+
+/// Where a search hit is in the tree.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HitLocation {
+    /// An area, by its bounds.
+    Area((usize, usize)),
+    Category(usize),
+    Item(Item),
+}
+
+/// An area, category or item matching a search query.
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchHit {
+    pub location: HitLocation,
+
+    /// The name that matched.
+    pub name: String,
+
+    /// How well the name matches, higher is better.
+    pub score: i64,
+
+    /// Matched parts of the name, as `[start, end)` character offsets.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<(usize, usize)>,
+}
+
+/// How a name matched.
+#[derive(Clone, Debug, Default)]
+pub struct NameMatch {
+    pub score: i64,
+    pub spans: Vec<(usize, usize)>,
+}
+
+/// Decides which names match during a search.
+/// A single matcher is shared by every thread of the traversal, so patterns are compiled once.
+pub trait Matcher: Sync {
+    fn match_area(&self, area: &Area) -> Option<NameMatch>;
+    fn match_category(&self, category: &Category) -> Option<NameMatch>;
+    fn match_item(&self, item: &Item) -> Option<NameMatch>;
+
+    /// Whether items of the category can match at all, so it can be skipped otherwise.
+    fn may_match_items(&self, _category: usize) -> bool {
+        true
+    }
+}
+
+/// A regex or glob matched against names, ignoring case.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    regex: Regex,
+
+    /// Globs match whole names, their literal parts are captured to report spans.
+    glob: bool,
+}
+
+/// Converts a byte offset of the name to a character offset.
+fn char_offset(name: &str, byte: usize) -> usize {
+    name[..byte].chars().count()
+}
+
+impl Pattern {
+    /// Compiles a regex, matching anywhere in names.
+    pub fn regex(pattern: &str) -> Result<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| anyhow!("invalid regex: {}", e))?;
+        Ok(Self { regex, glob: false })
+    }
+
+    /// Compiles a glob made of `*`, `?`, `[...]` classes and literal text, matching whole names.
+    pub fn glob(pattern: &str) -> Result<Self> {
+        let mut regex = String::from("^");
+        let mut literal = String::new();
+        let mut chars = pattern.chars();
+
+        let flush = |regex: &mut String, literal: &mut String| {
+            if !literal.is_empty() {
+                regex.push_str(&format!("({})", regex::escape(literal)));
+                literal.clear();
+            }
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => {
+                    flush(&mut regex, &mut literal);
+                    regex.push_str(".*");
+                }
+                '?' => {
+                    flush(&mut regex, &mut literal);
+                    regex.push('.');
+                }
+                '[' => {
+                    flush(&mut regex, &mut literal);
+                    let mut class = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') if !class.is_empty() => break,
+                            Some(c) => class.push(c),
+                            None => bail!("invalid glob '{}': unclosed character class", pattern),
+                        }
+                    }
+
+                    let (negated, class) = match class.strip_prefix('!') {
+                        Some(rest) => ("^", rest.to_string()),
+                        None => ("", class),
+                    };
+                    let class = class.replace('\\', "\\\\").replace('[', "\\[");
+                    regex.push_str(&format!("[{}{}]", negated, class));
+                }
+                c => literal.push(c),
+            }
+        }
+        flush(&mut regex, &mut literal);
+        regex.push('$');
+
+        let regex = RegexBuilder::new(&regex)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| anyhow!("invalid glob '{}': {}", pattern, e))?;
+        Ok(Self { regex, glob: true })
+    }
+
+    fn match_name(&self, name: &str) -> Option<NameMatch> {
+        let spans = if self.glob {
+            let captures = self.regex.captures(name)?;
+            captures
+                .iter()
+                .skip(1)
+                .flatten()
+                .map(|m| (char_offset(name, m.start()), char_offset(name, m.end())))
+                .collect()
+        } else {
+            let spans = self
+                .regex
+                .find_iter(name)
+                .filter(|m| !m.as_str().is_empty())
+                .map(|m| (char_offset(name, m.start()), char_offset(name, m.end())))
+                .collect::<Vec<_>>();
+
+            // Patterns that only match empty strings, like `^`, still match.
+            if spans.is_empty() && !self.regex.is_match(name) {
+                return None;
+            }
+            spans
+        };
+
+        Some(NameMatch { score: 0, spans })
+    }
+}
+
+impl Matcher for Pattern {
+    fn match_area(&self, area: &Area) -> Option<NameMatch> {
+        self.match_name(&area.name)
+    }
+
+    fn match_category(&self, category: &Category) -> Option<NameMatch> {
+        self.match_name(&category.name)
+    }
+
+    fn match_item(&self, item: &Item) -> Option<NameMatch> {
+        self.match_name(&item.name)
+    }
+}
+
+impl SearchHit {
+    pub(crate) fn new(location: HitLocation, name: &str, m: NameMatch) -> Self {
+        Self {
+            location,
+            name: String::from(name),
+            score: m.score,
+            spans: m.spans,
+        }
+    }
+
+    pub fn kind(&self) -> HitKind {
+        match self.location {
+            HitLocation::Area(_) => HitKind::Area,
+            HitLocation::Category(_) => HitKind::Category,
+            HitLocation::Item(_) => HitKind::Item,
+        }
+    }
+
+    /// Position of the hit in the tree, an area shares its position with its first category.
+    fn position(&self) -> (usize, usize) {
+        match &self.location {
+            HitLocation::Area(bounds) => (bounds.0, 0),
+            HitLocation::Category(category) => (*category, 0),
+            HitLocation::Item(item) => (item.id.category, item.id.id),
+        }
+    }
+}
+
+/// Lowercases a single character, keeping positions aligned with the original text.
+pub(crate) fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Whether the character at `j` starts a word, e.g. `f` in `tax-forms`, `F` in `taxForms` or `2` in `tax2024`.
+fn is_boundary(chars: &[char], j: usize) -> bool {
+    if j == 0 {
+        return true;
+    }
+
+    let (prev, cur) = (chars[j - 1], chars[j]);
+    !prev.is_alphanumeric()
+        || (prev.is_lowercase() && cur.is_uppercase())
+        || (prev.is_alphabetic() && cur.is_numeric())
+        || (prev.is_numeric() && cur.is_alphabetic())
+}
+
+/// Scores a single query term against a name, `None` if the term isn't a subsequence of the name.
+fn score_term(term: &[char], name: &[char], folded: &[char]) -> Option<i64> {
+    if term.is_empty() {
+        return Some(0);
+    }
+
+    // best[j]: best score of the term so far, with its last character matched at `j`.
+    let mut best = vec![NONE; name.len()];
+
+    for (i, t) in term.iter().enumerate() {
+        let mut next = vec![NONE; name.len()];
+
+        // Best score of an earlier match followed by at least one skipped character.
+        let mut gapped = NONE;
+
+        for j in 0..name.len() {
+            if j >= 2 && best[j - 2] > NONE {
+                gapped = gapped.max(best[j - 2]);
+            }
+            if gapped > NONE {
+                gapped -= GAP;
+            }
+
+            if folded[j] != *t {
+                continue;
+            }
+
+            // Word starts only count when they begin a run, so `InVoiCe` doesn't outrank `Invoice`.
+            let start = if j == 0 {
+                PREFIX
+            } else if is_boundary(name, j) {
+                BOUNDARY
+            } else {
+                0
+            };
+
+            let fresh = if i == 0 {
+                -(j as i64 * GAP).min(MAX_LEADING_GAP)
+            } else {
+                gapped
+            };
+            let consecutive = if i > 0 && j > 0 { best[j - 1] } else { NONE };
+
+            let score = match (fresh > NONE, consecutive > NONE) {
+                (true, true) => (fresh + start).max(consecutive + CONSECUTIVE),
+                (true, false) => fresh + start,
+                (false, true) => consecutive + CONSECUTIVE,
+                (false, false) => continue,
+            };
+            next[j] = score + MATCH;
+        }
+
+        best = next;
+    }
+
+    best.into_iter().filter(|s| *s > NONE).max()
+}
+
+/// Fuzzy-matches a query against a name, ignoring case.
+///
+/// Every whitespace-separated term of the query must appear in the name in order,
+/// though not necessarily contiguously. Matches on prefixes, word boundaries and
+/// runs of consecutive characters score higher.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let folded = chars.iter().map(|c| fold(*c)).collect::<Vec<_>>();
+
+    let mut score = 0;
+    for term in query.split_whitespace() {
+        let term = term.chars().map(fold).collect::<Vec<_>>();
+        score += score_term(&term, &chars, &folded)?;
+    }
+
+    Some(score)
+}
+
+/// Sorts hits from the best match to the worst, ties are kept in tree order.
+pub(crate) fn rank(hits: &mut [SearchHit]) {
+    hits.sort_by_key(|h| (Reverse(h.score), h.position(), h.kind()));
+}
@@ -0,0 +1,79 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ID;
+
+/// Checks that the tag can be written in a `tag:` query term, and returns it lowercase.
+pub(crate) fn normalize(tag: &str) -> Result<String> {
+    ensure!(
+        !tag.is_empty() && !tag.contains(|c: char| c.is_whitespace() || c == '"'),
+        "invalid tag '{}', tags are single words",
+        tag
+    );
+    Ok(tag.to_lowercase())
+}
+
+/// Tags of the items that have any, keyed by ID.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tags {
+    items: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Tags {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = fs::File::open(path.as_ref())?;
+        Ok(serde_json::from_reader(f)?)
+    }
+
+    /// Writes the tags through a temporary file, so an interrupted save keeps the previous ones.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        serde_json::to_writer(fs::File::create(&tmp)?, &self)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Tags of the item, sorted.
+    pub fn get(&self, id: &ID) -> Vec<String> {
+        self.items
+            .get(&id.to_string())
+            .map(|tags| tags.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn add(&mut self, id: &ID, tag: &str) -> Result<()> {
+        self.items
+            .entry(id.to_string())
+            .or_default()
+            .insert(normalize(tag)?);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: &ID, tag: &str) -> Result<()> {
+        let tag = normalize(tag)?;
+        if let Some(tags) = self.items.get_mut(&id.to_string()) {
+            tags.remove(&tag);
+            if tags.is_empty() {
+                self.items.remove(&id.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Hands the tags of an item over to the ID it was moved to.
+    pub fn rename(&mut self, old: &ID, new: &ID) {
+        if let Some(tags) = self.items.remove(&old.to_string()) {
+            self.items.insert(new.to_string(), tags);
+        }
+    }
+
+    pub fn forget(&mut self, id: &ID) {
+        self.items.remove(&id.to_string());
+    }
+}
@@ -0,0 +1,126 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ID;
+
+/// Once the ranks of all items add up to this, they are scaled down so old habits fade out.
+const MAX_TOTAL_RANK: f64 = 1000.0;
+
+/// Factor applied to every rank when aging.
+const AGING: f64 = 0.9;
+
+/// Items whose rank drops below this after aging are forgotten.
+const MIN_RANK: f64 = 1.0;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Seconds since the epoch.
+pub(crate) fn now() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// How often and how recently an item was used.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ItemUsage {
+    pub id: ID,
+
+    /// Grows by one on every use, and decays as other items get used.
+    pub rank: f64,
+
+    /// Number of times the item was used.
+    pub uses: u64,
+
+    /// When the item was last used, in seconds since the epoch.
+    pub last_used: u64,
+}
+
+impl ItemUsage {
+    /// Seconds since the item was last used.
+    pub fn age(&self) -> Result<u64> {
+        Ok(now()?.saturating_sub(self.last_used))
+    }
+
+    /// The rank weighted by how recently the item was used, the same way zoxide does.
+    pub fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_used);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+}
+
+/// Usage of the items that were opened or located, keyed by ID.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct UsageLog {
+    items: BTreeMap<String, ItemUsage>,
+}
+
+impl UsageLog {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let f = fs::File::open(path.as_ref())?;
+        Ok(serde_json::from_reader(f)?)
+    }
+
+    /// Writes the log through a temporary file, so an interrupted save keeps the previous log.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("json.tmp");
+        serde_json::to_writer(fs::File::create(&tmp)?, &self)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &ID) -> Option<&ItemUsage> {
+        self.items.get(&id.to_string())
+    }
+
+    /// Records a use of the item at the given time.
+    pub fn record(&mut self, id: &ID, now: u64) {
+        let usage = self
+            .items
+            .entry(id.to_string())
+            .or_insert_with(|| ItemUsage {
+                id: id.clone(),
+                rank: 0.0,
+                uses: 0,
+                last_used: now,
+            });
+        usage.rank += 1.0;
+        usage.uses += 1;
+        usage.last_used = now;
+
+        if self.items.values().map(|u| u.rank).sum::<f64>() > MAX_TOTAL_RANK {
+            for usage in self.items.values_mut() {
+                usage.rank *= AGING;
+            }
+            self.items.retain(|_, u| u.rank >= MIN_RANK);
+        }
+    }
+
+    pub fn forget(&mut self, id: &ID) {
+        self.items.remove(&id.to_string());
+    }
+
+    /// Lists the used items, most recently used first.
+    pub fn recent(&self) -> Vec<&ItemUsage> {
+        let mut items = self.items.values().collect::<Vec<_>>();
+        items.sort_by_key(|u| Reverse(u.last_used));
+        items
+    }
+}
@@ -24,5 +24,6 @@ fn main() {
 
     if let Err(e) = cli::Root::parse().run() {
         eprintln!("{}", e);
+        std::process::exit(1);
     }
 }
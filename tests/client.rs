@@ -0,0 +1,289 @@
+//! Exercises the client against in-memory resolvers, everything it writes stays in a scratch dir.
+mod common;
+
+use std::fs;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use johnny::{
+    Config, HitLocation, Index, JohnnyDecimal, Location, MemoryIndexBackend, MemoryResolver, Query,
+    Resolver, ResolverConfig, ResolverConstraint,
+};
+
+use common::Scratch;
+
+fn index() -> Result<Index> {
+    let mut index = Index::default();
+    let area = index.create_area_mut((10, 19), "Finance")?;
+    area.create_category(11, String::from("Invoices"))?;
+    area.create_category(12, String::from("Taxes"))?;
+    Ok(index)
+}
+
+fn client(scratch: &Scratch, backend: MemoryIndexBackend) -> Result<JohnnyDecimal> {
+    let config = Config {
+        index_path: scratch.path().join("index.json"),
+        resolvers: Vec::new(),
+        path: None,
+    };
+
+    JohnnyDecimal::builder()
+        .config(config)
+        .index(index()?)
+        .index_backend(Box::new(backend))
+        .resolver(ResolverConstraint::Default, Arc::new(MemoryResolver::new()))
+        .build()
+}
+
+/// A client filing each category with its own resolver.
+fn filed_by(scratch: &Scratch, resolvers: Vec<(usize, ResolverConfig)>) -> Result<JohnnyDecimal> {
+    let config = Config {
+        index_path: scratch.path().join("index.json"),
+        resolvers: resolvers
+            .into_iter()
+            .map(|(category, config)| Resolver {
+                name: None,
+                mirror: false,
+                constraint: ResolverConstraint::ID(category),
+                config,
+                fallbacks: Vec::new(),
+            })
+            .collect(),
+        path: None,
+    };
+
+    JohnnyDecimal::builder()
+        .config(config)
+        .index(index()?)
+        .index_backend(Box::new(MemoryIndexBackend::new()))
+        .build()
+}
+
+/// A client filing 11 on disk and 12 as links, both under the scratch dir.
+fn disk_and_links(scratch: &Scratch) -> Result<JohnnyDecimal> {
+    filed_by(
+        scratch,
+        vec![
+            (
+                11,
+                ResolverConfig::DiskResolver {
+                    root: scratch.path().join("disk"),
+                },
+            ),
+            (
+                12,
+                ResolverConfig::LinkResolver {
+                    link_root: scratch.path().join("links"),
+                },
+            ),
+        ],
+    )
+}
+
+#[test]
+fn alloc_url_is_located() -> Result<()> {
+    let scratch = Scratch::new();
+    let backend = MemoryIndexBackend::new();
+    let mut jd = client(&scratch, backend.clone())?;
+
+    let item = jd.alloc_url(11, "Bank", "https://bank.example")?;
+
+    assert_eq!(
+        jd.locate(&item.id)?,
+        Some(Location::URL(String::from("https://bank.example")))
+    );
+
+    let saved = backend.snapshot().expect("index was saved");
+    let category = saved
+        .get_area_from_category(11)?
+        .unwrap()
+        .get_category(11)?;
+    assert!(category.unwrap().get_item(&item.id)?.is_some());
+    Ok(())
+}
+
+#[test]
+fn rebuild_keeps_memory_items() -> Result<()> {
+    let scratch = Scratch::new();
+    let mut jd = client(&scratch, MemoryIndexBackend::new())?;
+
+    let bank = jd.alloc_url(11, "Bank", "https://bank.example")?;
+    let forms = jd.alloc_url(12, "Forms", "https://taxes.example")?;
+    jd.rename_category(12, "Tax returns")?;
+
+    jd.rebuild()?;
+
+    let area = jd.index.get_area_from_category(11)?.unwrap();
+    assert_eq!(area.name, "Finance");
+    assert_eq!(
+        area.get_category(11)?
+            .unwrap()
+            .get_item(&bank.id)?
+            .unwrap()
+            .name,
+        "Bank"
+    );
+
+    let taxes = area.get_category(12)?.unwrap();
+    assert_eq!(taxes.name, "Tax returns");
+    assert_eq!(taxes.get_item(&forms.id)?.unwrap().name, "Forms");
+    Ok(())
+}
+
+#[test]
+fn relocate_forgets_the_old_id() -> Result<()> {
+    let scratch = Scratch::new();
+    let mut jd = client(&scratch, MemoryIndexBackend::new())?;
+
+    let old = jd.alloc_url(11, "Bank", "https://bank.example")?;
+    jd.record_use(&old.id)?;
+    let moved = jd.relocate(&old.id, 12)?;
+
+    // The old ID may be handed out again.
+    assert!(jd.usage()?.get(&old.id).is_none());
+
+    assert_eq!(
+        jd.locate(&moved.id)?,
+        Some(Location::URL(String::from("https://bank.example")))
+    );
+
+    // A stale entry under the old ID would come back on rebuild.
+    jd.rebuild()?;
+    let area = jd.index.get_area_from_category(11)?.unwrap();
+    if let Some(old_category) = area.get_category(11)? {
+        assert!(old_category.list_items().is_empty());
+    }
+    assert!(area
+        .get_category(12)?
+        .unwrap()
+        .get_item(&moved.id)?
+        .is_some());
+    Ok(())
+}
+
+#[test]
+fn usage_stays_beside_the_index() -> Result<()> {
+    let scratch = Scratch::new();
+    let mut jd = client(&scratch, MemoryIndexBackend::new())?;
+
+    let item = jd.alloc_url(11, "Bank", "https://bank.example")?;
+    jd.record_use(&item.id)?;
+    jd.record_use(&item.id)?;
+
+    assert!(scratch.path().join("usage.json").exists());
+
+    let recent = jd.recent()?;
+    assert_eq!(recent.len(), 1);
+    assert_eq!(recent[0].0.name, "Bank");
+    assert_eq!(recent[0].1.uses, 2);
+    Ok(())
+}
+
+#[test]
+fn trash_refuses_url_items() -> Result<()> {
+    let scratch = Scratch::new();
+    let mut jd = client(&scratch, MemoryIndexBackend::new())?;
+
+    let item = jd.alloc_url(11, "Bank", "https://bank.example")?;
+    assert!(jd.trash(&item.id).is_err());
+
+    assert!(!scratch.path().join("trash").exists());
+    assert_eq!(
+        jd.locate(&item.id)?,
+        Some(Location::URL(String::from("https://bank.example")))
+    );
+    Ok(())
+}
+
+#[test]
+fn relocate_to_links_keeps_the_content() -> Result<()> {
+    let scratch = Scratch::new();
+    let mut jd = disk_and_links(&scratch)?;
+
+    let source = scratch.path().join("Statements");
+    fs::create_dir_all(&source)?;
+    fs::write(source.join("march.txt"), "balance")?;
+    let old = jd.mv(11, &source, None)?;
+
+    let moved = jd.relocate(&old.id, 12)?;
+
+    let link = match jd.locate(&moved.id)? {
+        Some(Location::Path(p)) => p,
+        other => panic!("unexpected location: {:?}", other),
+    };
+    assert_eq!(fs::read_to_string(link.join("march.txt"))?, "balance");
+    Ok(())
+}
+
+#[test]
+fn store_edits_leave_shared_content_alone() -> Result<()> {
+    let scratch = Scratch::new();
+    let mut jd = filed_by(
+        &scratch,
+        vec![(
+            11,
+            ResolverConfig::StoreResolver {
+                store_root: scratch.path().join("store"),
+            },
+        )],
+    )?;
+
+    let mut file = |name: &str| -> Result<johnny::Item> {
+        let source = scratch.path().join(name);
+        fs::create_dir_all(&source)?;
+        fs::write(source.join("terms.txt"), "v1")?;
+        jd.mv(11, &source, None)
+    };
+    let first = file("First")?;
+    let second = file("Second")?;
+
+    let checkout = jd.checkout(&first.id)?.expect("stored items check out");
+    let path = match &checkout.location {
+        Location::Path(p) => p.join("terms.txt"),
+        other => panic!("unexpected location: {:?}", other),
+    };
+    fs::write(&path, "v2")?;
+    jd.checkin(&first.id, checkout)?;
+
+    let read = |item: &johnny::Item| -> Result<String> {
+        let path = jd.local_path(&item.id)?.expect("stored items are local");
+        Ok(fs::read_to_string(path.join("terms.txt"))?)
+    };
+    assert_eq!(read(&first)?, "v2");
+    assert_eq!(read(&second)?, "v1");
+    Ok(())
+}
+
+#[test]
+fn tags_follow_their_item() -> Result<()> {
+    let scratch = Scratch::new();
+    let mut jd = client(&scratch, MemoryIndexBackend::new())?;
+
+    let forms = jd.alloc_url(11, "Forms", "https://taxes.example")?;
+    let bank = jd.alloc_url(11, "Bank", "https://bank.example")?;
+    assert_eq!(jd.tag(&forms.id, "Tax")?, vec![String::from("tax")]);
+    assert!(jd.tag(&forms.id, "two words").is_err());
+
+    let names = |jd: &JohnnyDecimal, query: &str| -> Result<Vec<String>> {
+        Ok(jd
+            .search(&query.parse::<Query>()?)?
+            .into_iter()
+            .filter_map(|hit| match hit.location {
+                HitLocation::Item(item) => Some(item.name),
+                _ => None,
+            })
+            .collect())
+    };
+    assert_eq!(names(&jd, "tag:tax")?, vec![String::from("Forms")]);
+    assert_eq!(names(&jd, "-tag:tax")?, vec![String::from("Bank")]);
+
+    let moved = jd.relocate(&forms.id, 12)?;
+    assert_eq!(jd.tags()?.get(&moved.id), vec![String::from("tax")]);
+    assert!(jd.tags()?.get(&forms.id).is_empty());
+
+    jd.rm(&moved.id)?;
+    assert!(jd.tags()?.get(&moved.id).is_empty());
+    assert!(jd.tags()?.get(&bank.id).is_empty());
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Checks how resolver tables are validated when building a client.
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use johnny::{Config, Index, JohnnyDecimal, MemoryIndexBackend, Resolver, ResolverConfig};
+
+fn resolver(constraint: &str, name: Option<&str>, config: ResolverConfig) -> Result<Resolver> {
+    Ok(Resolver {
+        name: name.map(String::from),
+        mirror: false,
+        constraint: serde_json::from_str(constraint)?,
+        config,
+        fallbacks: Vec::new(),
+    })
+}
+
+fn disk(root: &str) -> ResolverConfig {
+    ResolverConfig::DiskResolver {
+        root: PathBuf::from(root),
+    }
+}
+
+fn build(config: Config) -> Result<JohnnyDecimal> {
+    JohnnyDecimal::builder()
+        .config(config)
+        .index(Index::default())
+        .index_backend(Box::new(MemoryIndexBackend::new()))
+        .build()
+}
+
+fn config(resolvers: Vec<Resolver>) -> Config {
+    Config {
+        index_path: std::env::temp_dir()
+            .join("jd-test-config")
+            .join("index.json"),
+        resolvers,
+        path: None,
+    }
+}
+
+#[test]
+fn overlaps_go_to_the_earliest_resolver() -> Result<()> {
+    let config = config(vec![
+        resolver("[0, 99]", None, disk("/jd/disk"))?,
+        resolver(
+            "[30, 39]",
+            None,
+            ResolverConfig::GithubResolver { github_area: 30 },
+        )?,
+    ]);
+    assert_eq!(config.overlaps().len(), 1);
+
+    let jd = build(config)?;
+    assert_eq!(jd.describe_resolver(31).as_deref(), Some("disk /jd/disk"));
+    Ok(())
+}
+
+#[test]
+fn duplicate_names_are_rejected() -> Result<()> {
+    let table = || -> Result<Config> {
+        Ok(config(vec![
+            resolver("[0, 49]", Some("cold"), disk("/jd/a"))?,
+            resolver("[50, 99]", Some("cold"), disk("/jd/b"))?,
+        ]))
+    };
+    assert!(build(table()?).is_err());
+
+    let jd = JohnnyDecimal::builder()
+        .config(table()?)
+        .index(Index::default())
+        .index_backend(Box::new(MemoryIndexBackend::new()))
+        .skip_validation()
+        .build()?;
+    assert_eq!(jd.config().duplicate_names().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn catch_alls_only_warn() -> Result<()> {
+    let config = config(vec![
+        resolver("\"default\"", None, disk("/jd/default"))?,
+        resolver("{\"except\": {\"area\": 30}}", None, disk("/jd/except"))?,
+        resolver("{\"except\": {\"area\": 40}}", None, disk("/jd/other"))?,
+    ]);
+    let overlaps = config.overlaps();
+    assert_eq!(overlaps.iter().filter(|o| o.tie).count(), 1);
+    // The default is shadowed by both exceptions.
+    assert!(overlaps.iter().any(|o| o.served_by == 1 && o.shadowed == 0));
+
+    let jd = build(config)?;
+    assert_eq!(jd.describe_resolver(12).as_deref(), Some("disk /jd/except"));
+    assert_eq!(jd.describe_resolver(31).as_deref(), Some("disk /jd/other"));
+    Ok(())
+}
+
+#[test]
+fn assigning_never_copies_a_resolver() -> Result<()> {
+    let mut config = config(vec![
+        resolver("[0, 99]", None, disk("/jd/disk"))?,
+        resolver("[50, 59]", Some("range"), disk("/jd/range"))?,
+        resolver("{\"categories\": [90]}", Some("cold"), disk("/jd/cold"))?,
+    ]);
+
+    assert!(config.assign_category(11, "range").is_err());
+    assert_eq!(config.resolvers.len(), 3);
+
+    config.assign_category(11, "cold")?;
+    assert_eq!(config.resolvers.len(), 3);
+    assert_eq!(config.serving(11), Some(2));
+    Ok(())
+}
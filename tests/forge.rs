@@ -0,0 +1,112 @@
+//! Lists repositories from a fake Gitea instance served in-process.
+mod common;
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use johnny::{
+    Config, ForgeConfig, ForgeKind, Index, JohnnyDecimal, Location, MemoryIndexBackend, Resolver,
+    ResolverConfig, ResolverConstraint,
+};
+
+use common::{serve, Scratch};
+
+#[test]
+fn repos_are_listed_page_by_page() -> Result<()> {
+    let scratch = Scratch::new();
+    // The disk tree brings the area and its category, the forge only fills them.
+    fs::create_dir_all(
+        scratch
+            .path()
+            .join("disk")
+            .join("30-39 Code")
+            .join("31 Team"),
+    )?;
+
+    let pages = Arc::new(Mutex::new(Vec::new()));
+    let seen = pages.clone();
+    let url = serve(move |request| {
+        // Team is a user, not an org.
+        if request.path != "/api/v1/users/Team/repos" || request.param("limit") != Some("50") {
+            return ("404 Not Found", Vec::new());
+        }
+
+        let page = request
+            .param("page")
+            .unwrap_or("1")
+            .parse::<usize>()
+            .unwrap();
+        seen.lock().unwrap().push(page);
+        let count = match page {
+            1 => 50,
+            2 => 3,
+            _ => 0,
+        };
+        let repos = (0..count)
+            .map(|i| format!(r#"{{"name": "repo-{:02}"}}"#, (page - 1) * 50 + i))
+            .collect::<Vec<_>>();
+        ("200 OK", format!("[{}]", repos.join(",")).into_bytes())
+    });
+
+    let resolver = |constraint, config| Resolver {
+        name: None,
+        mirror: false,
+        constraint,
+        config,
+        fallbacks: Vec::new(),
+    };
+    let config = Config {
+        index_path: scratch.path().join("index.json"),
+        resolvers: vec![
+            resolver(
+                ResolverConstraint::Default,
+                ResolverConfig::DiskResolver {
+                    root: scratch.path().join("disk"),
+                },
+            ),
+            resolver(
+                ResolverConstraint::Area(30),
+                ResolverConfig::ForgeResolver(ForgeConfig {
+                    forge: ForgeKind::Gitea,
+                    forge_area: 30,
+                    base_url: Some(url.clone()),
+                    api_url: None,
+                    url_template: None,
+                    token_env: None,
+                }),
+            ),
+        ],
+        path: None,
+    };
+
+    let mut jd = JohnnyDecimal::builder()
+        .config(config)
+        .index(Index::default())
+        .index_backend(Box::new(MemoryIndexBackend::new()))
+        .build()?;
+    jd.rebuild()?;
+
+    // A short page ends the listing.
+    assert_eq!(*pages.lock().unwrap(), vec![1, 2]);
+
+    let items = jd
+        .index
+        .get_area_from_category(31)?
+        .expect("the area comes from disk")
+        .get_category(31)?
+        .expect("the category comes from disk")
+        .list_items();
+    assert_eq!(items.len(), 53);
+
+    let last = items
+        .iter()
+        .find(|i| i.name == "repo-52")
+        .expect("the second page is listed");
+    assert_eq!(
+        jd.locate(&last.id)?,
+        Some(Location::URL(format!("{}/Team/repo-52", url)))
+    );
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Clones items from a local repository through a `file://` remote.
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Result};
+
+use johnny::{
+    Config, Index, JohnnyDecimal, MemoryIndexBackend, Resolver, ResolverConfig, ResolverConstraint,
+};
+
+use common::Scratch;
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=jd", "-c", "user.email=jd@example.com"])
+        .args(args)
+        .status()?;
+    ensure!(status.success(), "git {} failed", args.join(" "));
+    Ok(())
+}
+
+fn client(scratch: &Scratch) -> Result<JohnnyDecimal> {
+    let config = Config {
+        index_path: scratch.path().join("index.json"),
+        resolvers: vec![Resolver {
+            name: None,
+            mirror: false,
+            constraint: ResolverConstraint::ID(11),
+            config: ResolverConfig::GitResolver {
+                git_root: scratch.path().join("code"),
+            },
+            fallbacks: Vec::new(),
+        }],
+        path: None,
+    };
+
+    let mut index = Index::default();
+    index
+        .create_area_mut((10, 19), "Code")?
+        .create_category(11, String::from("Tools"))?;
+
+    JohnnyDecimal::builder()
+        .config(config)
+        .index(index)
+        .index_backend(Box::new(MemoryIndexBackend::new()))
+        .build()
+}
+
+#[test]
+fn urls_are_cloned() -> Result<()> {
+    let scratch = Scratch::new();
+    let remote = scratch.path().join("remote");
+    fs::create_dir_all(&remote)?;
+    git(&remote, &["init", "--quiet", "--initial-branch=main"])?;
+    fs::write(remote.join("README"), "tools")?;
+    git(&remote, &["add", "README"])?;
+    git(
+        &remote,
+        &["commit", "--quiet", "--message", "Initial commit"],
+    )?;
+
+    let mut jd = client(&scratch)?;
+    let url = format!("file://{}", remote.display());
+    let item = jd.alloc_url(11, "Scripts", &url)?;
+
+    let path = jd.local_path(&item.id)?.expect("clones are local");
+    assert_eq!(fs::read_to_string(path.join("README"))?, "tools");
+
+    let status = jd.status(&item.id)?.expect("clones have a status");
+    assert_eq!(status.branch.as_deref(), Some("main"));
+    assert!(!status.dirty);
+
+    fs::write(path.join("notes.txt"), "todo")?;
+    assert!(jd.status(&item.id)?.expect("clones have a status").dirty);
+    Ok(())
+}
+
+#[test]
+fn urls_are_never_options() -> Result<()> {
+    let scratch = Scratch::new();
+    let marker = scratch.path().join("marker");
+
+    let mut jd = client(&scratch)?;
+    let url = format!("--upload-pack=touch {}", marker.display());
+    assert!(jd.alloc_url(11, "Scripts", &url).is_err());
+    assert!(!marker.exists());
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! Syncs items from a fake S3 endpoint served in-process, which doesn't check signatures.
+mod common;
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::Result;
+
+use percent_encoding::percent_decode_str;
+
+use johnny::{
+    Config, Index, JohnnyDecimal, Location, MemoryIndexBackend, Resolver, ResolverConfig,
+    ResolverConstraint, S3Config,
+};
+
+use common::{serve, Scratch};
+
+const ITEM_PREFIX: &str = "10-19 Finance/11 Invoices/11.001 Bank/";
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().to_string()
+}
+
+/// Drops the `..` segments of a key along with their parent, like HTTP clients do with URLs.
+fn normalize(key: &str) -> String {
+    let mut segments = Vec::new();
+    for segment in key.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." => {}
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Serves the objects from the `jd` bucket, answering listings and downloads only.
+fn bucket(objects: BTreeMap<String, String>) -> String {
+    serve(move |request| {
+        if let Some(prefix) = request.param("prefix") {
+            let prefix = decode(prefix);
+            let contents = objects
+                .iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .map(|(key, body)| {
+                    format!(
+                        "<Contents><Key>{}</Key><Size>{}</Size><ETag>\"{}\"</ETag><LastModified>2024-01-01T00:00:00.000Z</LastModified></Contents>",
+                        key,
+                        body.len(),
+                        body.len()
+                    )
+                })
+                .collect::<String>();
+            let listing = format!(
+                "<ListBucketResult>{}<IsTruncated>false</IsTruncated></ListBucketResult>",
+                contents
+            );
+            return ("200 OK", listing.into_bytes());
+        }
+
+        match request.path.strip_prefix("/jd/").and_then(|key| {
+            let key = decode(key);
+            objects
+                .iter()
+                .find(|(k, _)| normalize(k) == key)
+                .map(|(_, body)| body)
+        }) {
+            Some(body) => ("200 OK", body.clone().into_bytes()),
+            None => ("404 Not Found", Vec::new()),
+        }
+    })
+}
+
+fn client(scratch: &Scratch, endpoint: String) -> Result<JohnnyDecimal> {
+    std::env::set_var("JD_TEST_S3_ACCESS_KEY", "AKID");
+    std::env::set_var("JD_TEST_S3_SECRET_KEY", "SECRET");
+
+    let config = Config {
+        index_path: scratch.path().join("index.json"),
+        resolvers: vec![Resolver {
+            name: None,
+            mirror: false,
+            constraint: ResolverConstraint::ID(11),
+            config: ResolverConfig::S3Resolver(S3Config {
+                s3_bucket: String::from("jd"),
+                endpoint: Some(endpoint),
+                region: None,
+                prefix: None,
+                cache_dir: Some(scratch.path().join("cache")),
+                access_key_env: Some(String::from("JD_TEST_S3_ACCESS_KEY")),
+                secret_key_env: Some(String::from("JD_TEST_S3_SECRET_KEY")),
+                url_expiry: None,
+            }),
+            fallbacks: Vec::new(),
+        }],
+        path: None,
+    };
+
+    let mut index = Index::default();
+    index
+        .create_area_mut((10, 19), "Finance")?
+        .create_category(11, String::from("Invoices"))?;
+
+    JohnnyDecimal::builder()
+        .config(config)
+        .index(index)
+        .index_backend(Box::new(MemoryIndexBackend::new()))
+        .build()
+}
+
+fn bank(jd: &mut JohnnyDecimal) -> Result<johnny::Item> {
+    jd.index
+        .get_area_from_category_mut(11)?
+        .expect("the area exists")
+        .get_category_mut(11)?
+        .expect("the category exists")
+        .add_item("Bank", None)
+}
+
+#[test]
+fn objects_sync_into_the_cache() -> Result<()> {
+    let scratch = Scratch::new();
+    let objects = BTreeMap::from([
+        (format!("{}march.txt", ITEM_PREFIX), String::from("balance")),
+        (
+            format!("{}2024//april.txt", ITEM_PREFIX),
+            String::from("more"),
+        ),
+    ]);
+    let mut jd = client(&scratch, bucket(objects))?;
+    let item = bank(&mut jd)?;
+
+    let cached = match jd.locate(&item.id)? {
+        Some(Location::Path(p)) => p,
+        other => panic!("unexpected location: {:?}", other),
+    };
+    assert_eq!(fs::read_to_string(cached.join("march.txt"))?, "balance");
+    assert_eq!(
+        fs::read_to_string(cached.join("2024").join("april.txt"))?,
+        "more"
+    );
+    Ok(())
+}
+
+#[test]
+fn parent_keys_are_refused() -> Result<()> {
+    let scratch = Scratch::new();
+    let objects = BTreeMap::from([(
+        format!("{}../../escape.txt", ITEM_PREFIX),
+        String::from("gotcha"),
+    )]);
+    let mut jd = client(&scratch, bucket(objects))?;
+    let item = bank(&mut jd)?;
+
+    assert!(jd.locate(&item.id).is_err());
+    assert!(!scratch.path().join("escape.txt").exists());
+    assert!(!scratch.path().join("cache").join("escape.txt").exists());
+    Ok(())
+}
+
+#[test]
+fn absolute_keys_stay_in_the_cache() -> Result<()> {
+    let scratch = Scratch::new();
+    let target = scratch.path().join("escape.txt");
+    let objects = BTreeMap::from([(
+        format!("{}{}", ITEM_PREFIX, target.display()),
+        String::from("gotcha"),
+    )]);
+    let mut jd = client(&scratch, bucket(objects))?;
+    let item = bank(&mut jd)?;
+
+    let cached = match jd.locate(&item.id)? {
+        Some(Location::Path(p)) => p,
+        other => panic!("unexpected location: {:?}", other),
+    };
+    assert!(!target.exists());
+    let relative = target.strip_prefix("/")?;
+    assert_eq!(fs::read_to_string(cached.join(relative))?, "gotcha");
+    Ok(())
+}
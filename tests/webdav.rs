@@ -0,0 +1,164 @@
+//! Migrates items to a WebDAV share served from a scratch dir by a minimal in-process server.
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+
+use johnny::{
+    Config, Index, JohnnyDecimal, MemoryIndexBackend, Resolver, ResolverConfig, ResolverConstraint,
+    WebDavConfig,
+};
+
+use common::{serve, Request, Scratch};
+
+fn href(root: &Path, path: &Path) -> String {
+    let mut href = String::new();
+    for segment in path.strip_prefix(root).unwrap().components() {
+        href.push('/');
+        let segment = segment.as_os_str().to_string_lossy();
+        href.push_str(&utf8_percent_encode(&segment, NON_ALPHANUMERIC).to_string());
+    }
+    if path.is_dir() {
+        href.push('/');
+    }
+    href
+}
+
+fn propfind(root: &Path, path: &Path, depth: &str) -> Vec<u8> {
+    let mut paths = vec![path.to_path_buf()];
+    if depth == "1" && path.is_dir() {
+        paths.extend(fs::read_dir(path).unwrap().map(|e| e.unwrap().path()));
+    }
+
+    let mut body = String::from(r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">"#);
+    for path in paths {
+        let kind = if path.is_dir() { "<d:collection/>" } else { "" };
+        body.push_str(&format!(
+            "<d:response><d:href>{}</d:href><d:propstat><d:prop><d:resourcetype>{}</d:resourcetype></d:prop></d:propstat></d:response>",
+            href(root, &path),
+            kind
+        ));
+    }
+    body.push_str("</d:multistatus>");
+    body.into_bytes()
+}
+
+/// Answers the requests the resolver makes, keeping the share's files under `root`.
+fn webdav(root: &Path, request: &Request) -> std::io::Result<(&'static str, Vec<u8>)> {
+    let decoded = percent_decode_str(request.path.trim_matches('/'))
+        .decode_utf8_lossy()
+        .to_string();
+    let path = root.join(decoded);
+
+    Ok(match request.method.as_str() {
+        "PROPFIND" if path.exists() => (
+            "207 Multi-Status",
+            propfind(root, &path, request.header("Depth").unwrap_or("0")),
+        ),
+        "MKCOL" if path.exists() => ("405 Method Not Allowed", Vec::new()),
+        "MKCOL" => {
+            fs::create_dir(&path)?;
+            ("201 Created", Vec::new())
+        }
+        "PUT" => {
+            fs::write(&path, &request.body)?;
+            ("201 Created", Vec::new())
+        }
+        "GET" if path.is_file() => ("200 OK", fs::read(&path)?),
+        "DELETE" if path.is_dir() => {
+            fs::remove_dir_all(&path)?;
+            ("204 No Content", Vec::new())
+        }
+        "DELETE" if path.is_file() => {
+            fs::remove_file(&path)?;
+            ("204 No Content", Vec::new())
+        }
+        _ => ("404 Not Found", Vec::new()),
+    })
+}
+
+#[test]
+fn folders_migrate_to_webdav() -> Result<()> {
+    let scratch = Scratch::new();
+    let share = scratch.path().join("share");
+    fs::create_dir_all(share.join("files"))?;
+    let root = share.clone();
+    let url = serve(move |request| {
+        webdav(&root, request).unwrap_or(("500 Internal Server Error", Vec::new()))
+    });
+
+    let resolver = |name: &str, constraint, config| Resolver {
+        name: Some(String::from(name)),
+        mirror: false,
+        constraint,
+        config,
+        fallbacks: Vec::new(),
+    };
+    let config = Config {
+        index_path: scratch.path().join("index.json"),
+        resolvers: vec![
+            resolver(
+                "disk",
+                ResolverConstraint::ID(11),
+                ResolverConfig::DiskResolver {
+                    root: scratch.path().join("disk"),
+                },
+            ),
+            resolver(
+                "share",
+                ResolverConstraint::Categories(vec![12]),
+                ResolverConfig::WebDavResolver(WebDavConfig {
+                    webdav_url: format!("{}/files", url),
+                    username: None,
+                    password: None,
+                    username_env: Some(String::from("JD_TEST_NO_WEBDAV_USERNAME")),
+                    password_env: Some(String::from("JD_TEST_NO_WEBDAV_PASSWORD")),
+                }),
+            ),
+        ],
+        path: Some(scratch.path().join("config.json")),
+    };
+
+    let mut index = Index::default();
+    let area = index.create_area_mut((10, 19), "Finance")?;
+    area.create_category(11, String::from("Invoices"))?;
+    area.create_category(12, String::from("Taxes"))?;
+
+    let mut jd = JohnnyDecimal::builder()
+        .config(config)
+        .index(index)
+        .index_backend(Box::new(MemoryIndexBackend::new()))
+        .build()?;
+
+    let source = scratch.path().join("Statements");
+    fs::create_dir_all(source.join("2024"))?;
+    fs::write(source.join("march.txt"), "balance")?;
+    fs::write(source.join("2024").join("april.txt"), "more balance")?;
+    let item = jd.mv(11, &source, None)?;
+
+    let migrated = jd.migrate_category(11, "share")?;
+    assert_eq!(migrated.len(), 1);
+
+    let stored = share
+        .join("files")
+        .join("10-19 Finance")
+        .join("11 Invoices")
+        .join(format!("{}", item));
+    assert_eq!(fs::read_to_string(stored.join("march.txt"))?, "balance");
+    assert_eq!(
+        fs::read_to_string(stored.join("2024").join("april.txt"))?,
+        "more balance"
+    );
+    assert!(!scratch
+        .path()
+        .join("disk")
+        .join("10-19 Finance")
+        .join("11 Invoices")
+        .join(format!("{}", item))
+        .exists());
+    Ok(())
+}